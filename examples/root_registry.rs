@@ -1,4 +1,4 @@
-use sai::{component_registry, Component};
+use sai::component_registry;
 
 use crate::gotham_server::GothamServer;
 use crate::foo_controller::FooController;
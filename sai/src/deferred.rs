@@ -0,0 +1,88 @@
+use std::any::{Any, TypeId};
+use std::marker::PhantomData;
+use std::sync::Arc;
+use super::{ComponentRepository, Dependency, Injected};
+use super::dependency::downcast_component;
+use super::lazy::LazyContext;
+
+/// A dependency that is resolved after all components are built, declared with
+/// `#[injected(deferred)]`.
+///
+/// It doesn't affect the order of startup, so two components can depend on each other as long
+/// as one side is deferred:
+/// ```
+/// use sai::{Component, Deferred, Injected};
+///
+/// #[derive(Component)]
+/// struct EventBus {
+///     #[injected]
+///     handler: Injected<Handler>
+/// }
+///
+/// #[derive(Component)]
+/// struct Handler {
+///     #[injected(deferred)]
+///     bus: Deferred<Injected<EventBus>>
+/// }
+/// ```
+///
+/// The component is available once the system has started, i.e. from `post_start` on.
+/// A Deferred doesn't keep the component alive, so it doesn't form a reference cycle either.
+pub struct Deferred<T> {
+    type_id: TypeId,
+    context: Arc<LazyContext>,
+    __type: PhantomData<fn() -> T>
+}
+
+impl<T: Any + Send + Sync> Deferred<Injected<T>> {
+    /// Get the component, or `None` if it hasn't started yet or the system has stopped
+    pub fn try_get(&self) -> Option<Injected<T>> {
+        let component = self.context.find(self.type_id)?;
+        downcast_component(&component)
+    }
+
+    /// Get the component. Panics if it's not available, see `try_get`.
+    pub fn get(&self) -> Injected<T> {
+        self.try_get().unwrap_or_else(|| panic!(
+            "Deferred {} is not available, it's only resolved after the system has started",
+            std::any::type_name::<T>()
+        ))
+    }
+}
+
+impl<T> Clone for Deferred<T> {
+    fn clone(&self) -> Self {
+        Deferred {
+            type_id: self.type_id,
+            context: self.context.clone(),
+            __type: PhantomData
+        }
+    }
+}
+
+impl<T: Any + Send + Sync> Dependency for Deferred<Injected<T>> {
+    fn type_id() -> TypeId {
+        TypeId::of::<Deferred<Injected<T>>>()
+    }
+
+    /// Resolved after startup, so it doesn't affect the order of startup
+    fn depends_on() -> Option<TypeId> {
+        None
+    }
+
+    fn requires() -> Option<(TypeId, &'static str)> {
+        Some((TypeId::of::<Injected<T>>(), std::any::type_name::<T>()))
+    }
+
+    fn resolve(repository: &ComponentRepository) -> Self {
+        let context = repository
+            .get_by_typeid::<Arc<LazyContext>>(TypeId::of::<LazyContext>())
+            .expect("Deferred components can only be injected by a started System");
+
+        Deferred {
+            type_id: TypeId::of::<Injected<T>>(),
+            context: context.clone(),
+            __type: PhantomData
+        }
+    }
+}
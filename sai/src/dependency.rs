@@ -0,0 +1,112 @@
+use std::any::{Any, TypeId};
+use super::{Component, ComponentRepository, Injected};
+use super::provider::Provided;
+
+/// A value that a System can inject into a component, e.g. `Injected<T>`.
+///
+/// This is what fields marked with `#[injected]` and parameters of a component constructor
+/// are resolved with.
+pub trait Dependency: Sized {
+    /// Identifies the dependency, e.g. `TypeId::of::<Injected<T>>()`
+    fn type_id() -> TypeId;
+
+    /// The component that has to be started before this dependency can be resolved, if any
+    fn depends_on() -> Option<TypeId> {
+        Some(<Self as Dependency>::type_id())
+    }
+
+    /// What has to be registered for this dependency to be resolved, and its name.
+    /// Unlike `depends_on`, it includes components that are resolved on demand.
+    fn requires() -> Option<(TypeId, &'static str)> {
+        <Self as Dependency>::depends_on().map(|type_id| (type_id, std::any::type_name::<Self>()))
+    }
+
+    fn resolve(repository: &ComponentRepository) -> Self;
+}
+
+impl<T: Any + Send + Sync> Dependency for Injected<T> {
+    fn type_id() -> TypeId {
+        TypeId::of::<Injected<T>>()
+    }
+
+    fn requires() -> Option<(TypeId, &'static str)> {
+        Some((TypeId::of::<Injected<T>>(), std::any::type_name::<T>()))
+    }
+
+    fn resolve(repository: &ComponentRepository) -> Self {
+        let component: &Injected<dyn Component> = repository
+            .get_by_typeid(<Self as Dependency>::type_id())
+            .unwrap_or_else(|| panic!(
+                "Unable to find type {} in component repository",
+                std::any::type_name::<Self>()
+            ));
+
+        downcast_component(component)
+            .unwrap_or_else(|| panic!(
+                "Found {} in component repository. But unable to downcast it",
+                std::any::type_name::<Self>()
+            ))
+    }
+}
+
+/// Get the value of a started component, which is either the component itself or the value
+/// it provides, see `#[sai::provider]`.
+pub(crate) fn downcast_component<T: Any + Send + Sync>(
+    component: &Injected<dyn Component>
+) -> Option<Injected<T>> {
+    if let Some(provided) = (**component).as_any().downcast_ref::<Provided<T>>() {
+        return provided.get();
+    }
+    component.clone().downcast()
+}
+
+/// A function that can build a component from its dependencies.
+///
+/// It's implemented for any function whose parameters are all [Dependency](trait.Dependency.html),
+/// check out `#[component(constructor = "...")]` in the doc of [Component](trait.Component.html).
+pub trait Constructor<Args, Output> {
+    /// Dependencies derived from the parameters of the function
+    fn depends_on(&self) -> Vec<TypeId>;
+
+    /// What has to be registered, see `Dependency::requires`
+    fn requires(&self) -> Vec<(TypeId, &'static str)>;
+
+    fn construct(&self, repository: &ComponentRepository) -> Output;
+}
+
+macro_rules! impl_constructor {
+    ($($arg:ident),*) => {
+        impl<F, O, $($arg),*> Constructor<($($arg,)*), O> for F
+            where F: Fn($($arg),*) -> O, $($arg: Dependency),*
+        {
+            fn depends_on(&self) -> Vec<TypeId> {
+                let depends_on: Vec<Option<TypeId>> = vec![ $(<$arg as Dependency>::depends_on()),* ];
+                depends_on.into_iter().flatten().collect()
+            }
+
+            fn requires(&self) -> Vec<(TypeId, &'static str)> {
+                let requires: Vec<Option<(TypeId, &'static str)>> = vec![ $(<$arg as Dependency>::requires()),* ];
+                requires.into_iter().flatten().collect()
+            }
+
+            #[allow(unused_variables)]
+            fn construct(&self, repository: &ComponentRepository) -> O {
+                (self)($(<$arg as Dependency>::resolve(repository)),*)
+            }
+        }
+    }
+}
+
+impl_constructor!();
+impl_constructor!(A1);
+impl_constructor!(A1, A2);
+impl_constructor!(A1, A2, A3);
+impl_constructor!(A1, A2, A3, A4);
+impl_constructor!(A1, A2, A3, A4, A5);
+impl_constructor!(A1, A2, A3, A4, A5, A6);
+impl_constructor!(A1, A2, A3, A4, A5, A6, A7);
+impl_constructor!(A1, A2, A3, A4, A5, A6, A7, A8);
+impl_constructor!(A1, A2, A3, A4, A5, A6, A7, A8, A9);
+impl_constructor!(A1, A2, A3, A4, A5, A6, A7, A8, A9, A10);
+impl_constructor!(A1, A2, A3, A4, A5, A6, A7, A8, A9, A10, A11);
+impl_constructor!(A1, A2, A3, A4, A5, A6, A7, A8, A9, A10, A11, A12);
@@ -0,0 +1,205 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Condvar, Mutex};
+use std::task::{Context, Poll, Wake, Waker};
+use std::thread::{self, Thread};
+use std::time::{Duration, Instant};
+
+use once_cell::sync::OnceCell;
+
+/*
+ * A minimal executor to drive async shutdown logic from synchronous code, e.g. `Drop`.
+ *
+ * It blocks the current thread until the future completes, so it must not be used to wait for
+ * work that can only make progress on the current thread.
+ *
+ * Plus a runtime agnostic timer, as sai doesn't depend on any async runtime.
+ */
+
+struct ThreadWaker(Thread);
+
+impl Wake for ThreadWaker {
+    fn wake(self: Arc<Self>) {
+        self.0.unpark();
+    }
+}
+
+pub(crate) fn block_on<F: Future>(future: F) -> F::Output {
+    let mut future = Box::pin(future);
+    let waker = Waker::from(Arc::new(ThreadWaker(thread::current())));
+    let mut cx = Context::from_waker(&waker);
+
+    loop {
+        match future.as_mut().poll(&mut cx) {
+            Poll::Ready(output) => return output,
+            Poll::Pending => thread::park()
+        }
+    }
+}
+
+/*
+ * Completes after the duration. All sleeps share one long-lived timer thread, which wakes
+ * them in turn, so it's only meant for coarse waits like polling during the stop timeout.
+ */
+pub(crate) struct Sleep {
+    until: Instant,
+    id: Option<u64>
+}
+
+pub(crate) fn sleep(duration: Duration) -> Sleep {
+    Sleep {
+        until: Instant::now() + duration,
+        id: None
+    }
+}
+
+impl Future for Sleep {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        if Instant::now() >= self.until {
+            return Poll::Ready(());
+        }
+        let id = timer().schedule(self.id, self.until, cx.waker());
+        self.id = Some(id);
+        Poll::Pending
+    }
+}
+
+impl Drop for Sleep {
+    fn drop(&mut self) {
+        if let Some(id) = self.id {
+            timer().cancel(id);
+        }
+    }
+}
+
+struct Timer {
+    entries: Mutex<TimerEntries>,
+    changed: Condvar
+}
+
+#[derive(Default)]
+struct TimerEntries {
+    next_id: u64,
+    waiting: Vec<(u64, Instant, Waker)>
+}
+
+fn timer() -> &'static Timer {
+    static TIMER: OnceCell<Timer> = OnceCell::new();
+    TIMER.get_or_init(|| {
+        /* The thread waits in `timer()` until the initialization below is done */
+        thread::Builder::new()
+            .name("sai-timer".to_string())
+            .spawn(|| timer().run())
+            .expect("Failed to spawn the sai timer thread");
+        Timer {
+            entries: Mutex::new(TimerEntries::default()),
+            changed: Condvar::new()
+        }
+    })
+}
+
+impl Timer {
+    /* Add a new entry, or update the waker of an existing one. Returns the id of the entry */
+    fn schedule(&self, id: Option<u64>, until: Instant, waker: &Waker) -> u64 {
+        let mut entries = self.entries.lock().unwrap();
+        if let Some(id) = id {
+            if let Some(entry) = entries.waiting.iter_mut().find(|e| e.0 == id) {
+                if !entry.2.will_wake(waker) {
+                    entry.2 = waker.clone();
+                }
+                return id;
+            }
+        }
+
+        let id = entries.next_id;
+        entries.next_id += 1;
+        entries.waiting.push((id, until, waker.clone()));
+        self.changed.notify_one();
+        id
+    }
+
+    fn cancel(&self, id: u64) {
+        let mut entries = self.entries.lock().unwrap();
+        entries.waiting.retain(|e| e.0 != id);
+    }
+
+    fn run(&self) {
+        let mut entries = self.entries.lock().unwrap();
+        loop {
+            let now = Instant::now();
+            let (elapsed, waiting) = std::mem::take(&mut entries.waiting)
+                .into_iter()
+                .partition::<Vec<_>, _>(|e| e.1 <= now);
+            entries.waiting = waiting;
+
+            if !elapsed.is_empty() {
+                /* Wake without holding the lock, wakers may schedule again right away */
+                drop(entries);
+                elapsed.into_iter().for_each(|(_, _, waker)| waker.wake());
+                entries = self.entries.lock().unwrap();
+                continue;
+            }
+
+            entries = match entries.waiting.iter().map(|e| e.1).min() {
+                Some(next) => self.changed.wait_timeout(entries, next - now).unwrap().0,
+                None => self.changed.wait(entries).unwrap()
+            };
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn block_on_ready() {
+        assert_eq!(block_on(async { 42 }), 42);
+    }
+
+    #[test]
+    fn sleep_elapses() {
+        let start = Instant::now();
+        block_on(sleep(Duration::from_millis(20)));
+        assert!(start.elapsed() >= Duration::from_millis(20));
+    }
+
+    #[test]
+    fn sleeps_share_the_timer() {
+        let start = Instant::now();
+        let handles: Vec<_> = (1..=4u64)
+            .map(|i| thread::spawn(move || block_on(sleep(Duration::from_millis(10 * i)))))
+            .collect();
+        block_on(sleep(Duration::from_millis(5)));
+        handles.into_iter().for_each(|h| h.join().unwrap());
+        assert!(start.elapsed() >= Duration::from_millis(40));
+    }
+
+    struct WakeOnce {
+        sender: Option<std::sync::mpsc::Sender<Waker>>
+    }
+
+    impl Future for WakeOnce {
+        type Output = ();
+
+        fn poll(mut self: std::pin::Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+            match self.sender.take() {
+                Some(sender) => {
+                    sender.send(cx.waker().clone()).unwrap();
+                    Poll::Pending
+                },
+                None => Poll::Ready(())
+            }
+        }
+    }
+
+    #[test]
+    fn block_on_woken_by_other_thread() {
+        let (sender, receiver) = std::sync::mpsc::channel::<Waker>();
+        let handle = thread::spawn(move || receiver.recv().unwrap().wake());
+        block_on(WakeOnce { sender: Some(sender) });
+        handle.join().unwrap();
+    }
+}
@@ -0,0 +1,201 @@
+use std::any::TypeId;
+use std::collections::VecDeque;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::sync::mpsc::{self, Sender};
+use std::task::{Context, Poll, Waker};
+use std::time::Duration;
+use super::{ComponentRepository, ComponentStatus, Dependency, OrderedDependency, Registry, RestartError};
+use super::status::{self, Tracker};
+
+/// A handle to the System that a component belongs to, for components that report on or
+/// control the system itself, e.g. an admin endpoint.
+///
+/// ```
+/// use sai::{Component, SystemHandle};
+///
+/// #[derive(Component)]
+/// struct HealthCheck {
+///     #[injected]
+///     system: SystemHandle
+/// }
+///
+/// impl HealthCheck {
+///     fn healthy(&self) -> bool {
+///         self.system.is_up()
+///     }
+/// }
+/// ```
+///
+/// Requests to stop the system or restart a component are handled by whoever owns the
+/// System, see `System::handle_requests`. Only a [System](struct.System.html) provides a
+/// handle, the typed systems of `sai::system!` don't.
+#[derive(Clone)]
+pub struct SystemHandle {
+    registry: Arc<Registry>,
+    tracker: Arc<Tracker>,
+    requests: Arc<Requests>
+}
+
+impl SystemHandle {
+
+    pub(crate) fn new(registry: Arc<Registry>, tracker: Arc<Tracker>, requests: Arc<Requests>) -> Self {
+        SystemHandle {
+            registry,
+            tracker,
+            requests
+        }
+    }
+
+    /// Whether every component has started and the system is not stopping
+    pub fn is_up(&self) -> bool {
+        self.tracker.is_up()
+    }
+
+    /// The state of every component of the system, see `System::status`
+    pub fn status(&self) -> Vec<ComponentStatus> {
+        status::status(&self.registry, &self.tracker)
+    }
+
+    /// Ask the system to stop gracefully, i.e. make `System::handle_requests` return
+    pub fn request_stop(&self) {
+        self.requests.push(Request::Stop);
+    }
+
+    /// Restart a component by its name in `status`, see `System::restart`.
+    ///
+    /// This blocks the current thread until `System::handle_requests` has handled the request,
+    /// so it's meant for threads of their own, e.g. one serving a control socket. It gives up
+    /// with `RestartError::Timeout` after `timeout`, and the request is withdrawn unless the
+    /// restart has begun already.
+    pub fn restart(&self, name: &str, timeout: Duration) -> Result<Vec<&'static str>, RestartError> {
+        let type_id = self.registry
+            .all()
+            .into_iter()
+            .find(|tid| self.registry.get(*tid).map(|meta| meta.name) == Some(name))
+            .ok_or_else(|| RestartError::NotFound(name.to_string()))?;
+
+        let (sender, receiver) = mpsc::channel();
+        let id = self.requests.push_restart(type_id, sender);
+
+        match receiver.recv_timeout(timeout) {
+            Ok(result) => result,
+            Err(mpsc::RecvTimeoutError::Timeout) => {
+                if self.requests.withdraw(id) {
+                    return Err(RestartError::Timeout);
+                }
+                // Handled meanwhile, or being handled
+                receiver.try_recv().unwrap_or(Err(RestartError::Timeout))
+            },
+            // The request is dropped if the system stops first
+            Err(mpsc::RecvTimeoutError::Disconnected) => Err(RestartError::NotStarted)
+        }
+    }
+}
+
+impl Dependency for SystemHandle {
+    fn type_id() -> TypeId {
+        TypeId::of::<SystemHandle>()
+    }
+
+    /// The system is there before any of its components
+    fn depends_on() -> Option<TypeId> {
+        None
+    }
+
+    fn resolve(repository: &ComponentRepository) -> Self {
+        repository
+            .get_by_typeid::<SystemHandle>(TypeId::of::<SystemHandle>())
+            .expect("SystemHandle can only be injected by a started System")
+            .clone()
+    }
+}
+
+impl<R> OrderedDependency<R> for SystemHandle {
+    const DEPTH: usize = 0;
+}
+
+/*
+ * Requests made through a SystemHandle, handled by `System::handle_requests`
+ */
+pub(crate) enum Request {
+    Stop,
+    /* Identified by an id, so that the request can be withdrawn */
+    Restart(u64, TypeId, Sender<Result<Vec<&'static str>, RestartError>>)
+}
+
+#[derive(Default)]
+pub(crate) struct Requests {
+    state: Mutex<RequestState>
+}
+
+#[derive(Default)]
+struct RequestState {
+    queue: VecDeque<Request>,
+    next_id: u64,
+    waker: Option<Waker>
+}
+
+impl Requests {
+
+    fn push(&self, request: Request) {
+        let waker = {
+            let mut state = self.state.lock().unwrap();
+            state.queue.push_back(request);
+            state.waker.take()
+        };
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+    }
+
+    fn push_restart(&self, type_id: TypeId, reply: Sender<Result<Vec<&'static str>, RestartError>>) -> u64 {
+        let id = {
+            let mut state = self.state.lock().unwrap();
+            state.next_id += 1;
+            state.next_id
+        };
+        self.push(Request::Restart(id, type_id, reply));
+        id
+    }
+
+    /// Remove a restart request that is still queued. False if it's been taken already.
+    fn withdraw(&self, id: u64) -> bool {
+        let mut state = self.state.lock().unwrap();
+        let before = state.queue.len();
+        state.queue.retain(|request| !matches!(request, Request::Restart(other, _, _) if *other == id));
+        state.queue.len() < before
+    }
+
+    /// Wait for the next request
+    pub(crate) fn next(&self) -> Next<'_> {
+        Next {
+            requests: self
+        }
+    }
+
+    /// Drop pending requests, so that pending restarts fail
+    pub(crate) fn clear(&self) {
+        self.state.lock().unwrap().queue.clear();
+    }
+}
+
+pub(crate) struct Next<'a> {
+    requests: &'a Requests
+}
+
+impl Future for Next<'_> {
+    type Output = Request;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Request> {
+        let mut state = self.requests.state.lock().unwrap();
+        match state.queue.pop_front() {
+            Some(request) => Poll::Ready(request),
+            None => {
+                state.waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
@@ -22,9 +22,11 @@ impl<T> Injected<T> {
 
 impl<T: Downcast + 'static + ?Sized> Injected<T> {
     pub fn downcast<S: Any + Send + Sync>(self) -> Option<Injected<S>> {
-        return Some(Injected {
-            item: self.item.into_any_arc().downcast().unwrap(),
-        })
+        self.item
+            .into_any_arc()
+            .downcast()
+            .ok()
+            .map(Injected::from_arc)
     }
 }
 
@@ -57,7 +59,13 @@ impl<T: ?Sized> From<Box<T>> for Injected<T> {
     }
 }
 
-impl<T> Deref for Injected<T> {
+impl<T: ?Sized> From<Arc<T>> for Injected<T> {
+    fn from(arc: Arc<T>) -> Self {
+        Injected::from_arc(arc)
+    }
+}
+
+impl<T: ?Sized> Deref for Injected<T> {
     type Target = Arc<T>;
 
     fn deref(&self) -> &Arc<T> {
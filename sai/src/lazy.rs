@@ -0,0 +1,300 @@
+use std::any::{Any, TypeId};
+use std::collections::{HashMap, HashSet};
+use std::future::Future;
+use std::marker::PhantomData;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex, Weak};
+use std::task::{Context, Poll, Waker};
+use super::{Component, ComponentRepository, ComponentScope, Dependency, Injected, Registry};
+use super::dependency::downcast_component;
+use super::system::StartedComponent;
+use super::status::Tracker;
+
+/// A handle to a `#[component(lazy)]` component, which is built and started (together with
+/// its dependencies) on first access rather than during `System::start`.
+///
+/// ```ignore
+/// #[derive(Component)]
+/// #[component(lazy)]
+/// struct ReportGenerator {}
+///
+/// #[derive(Component)]
+/// struct Admin {
+///     #[injected]
+///     reports: Lazy<Injected<ReportGenerator>>
+/// }
+///
+/// let reports: Injected<ReportGenerator> = admin.reports.get().await;
+/// ```
+///
+/// A lazy component that has been started is stopped by `System::stop` like any other.
+/// Note that `get` must not be called from the `start` of another lazy component.
+pub struct Lazy<T> {
+    type_id: TypeId,
+    context: Arc<LazyContext>,
+    __type: PhantomData<fn() -> T>
+}
+
+impl<T: Any + Send + Sync> Lazy<Injected<T>> {
+    /// Get the component, building and starting it if this is the first access
+    pub async fn get(&self) -> Injected<T> {
+        let component = self.context.get(self.type_id).await;
+        downcast_component(&component)
+            .unwrap_or_else(|| panic!(
+                "Started {} lazily. But unable to downcast it",
+                std::any::type_name::<T>()
+            ))
+    }
+}
+
+impl<T> Clone for Lazy<T> {
+    fn clone(&self) -> Self {
+        Lazy {
+            type_id: self.type_id,
+            context: self.context.clone(),
+            __type: PhantomData
+        }
+    }
+}
+
+impl<T: Any + Send + Sync> Dependency for Lazy<Injected<T>> {
+    fn type_id() -> TypeId {
+        TypeId::of::<Lazy<Injected<T>>>()
+    }
+
+    /// The component is started on demand, so it doesn't affect the order of startup
+    fn depends_on() -> Option<TypeId> {
+        None
+    }
+
+    fn requires() -> Option<(TypeId, &'static str)> {
+        Some((TypeId::of::<Injected<T>>(), std::any::type_name::<T>()))
+    }
+
+    fn resolve(repository: &ComponentRepository) -> Self {
+        let context = repository
+            .get_by_typeid::<Arc<LazyContext>>(TypeId::of::<LazyContext>())
+            .expect("Lazy components can only be injected by a started System");
+
+        Lazy {
+            type_id: TypeId::of::<Injected<T>>(),
+            context: context.clone(),
+            __type: PhantomData
+        }
+    }
+}
+
+/*
+ * Everything needed to start lazy components while the system is running.
+ * There is one per started System, and it's closed when the system stops.
+ */
+pub(crate) struct LazyContext {
+    registry: Arc<Registry>,
+
+    /*
+     * Owned by the System, so lazy components are stopped along with the others.
+     * Weak, as started components may hold a `Lazy` pointing back to this context.
+     */
+    started: Weak<Mutex<Vec<StartedComponent>>>,
+
+    tracker: Arc<Tracker>,
+
+    state: Mutex<LazyState>
+}
+
+#[derive(Default)]
+struct LazyState {
+    /* Started singletons, eager and lazy */
+    components: HashMap<TypeId, Injected<dyn Component>>,
+    /* Whether post_start of the system has run */
+    up: bool,
+    closed: bool,
+    /* Only one lazy startup at a time */
+    starting: bool,
+    waiters: Vec<Waker>
+}
+
+impl LazyContext {
+
+    pub(crate) fn new(
+        registry: Arc<Registry>,
+        started: &Arc<Mutex<Vec<StartedComponent>>>,
+        tracker: Arc<Tracker>
+    ) -> Self {
+        LazyContext {
+            registry,
+            started: Arc::downgrade(started),
+            tracker,
+            state: Mutex::new(LazyState::default())
+        }
+    }
+
+    /// The registry of the system
+    pub(crate) fn registry(&self) -> &Registry {
+        &self.registry
+    }
+
+    /// Make a started singleton available to lazy components
+    pub(crate) fn insert(&self, type_id: TypeId, component: Injected<dyn Component>) {
+        self.state.lock().unwrap().components.insert(type_id, component);
+    }
+
+    /// Forget a singleton that is being restarted
+    pub(crate) fn remove(&self, type_id: TypeId) {
+        self.state.lock().unwrap().components.remove(&type_id);
+    }
+
+    /// A started singleton, eager or lazy
+    pub(crate) fn find(&self, type_id: TypeId) -> Option<Injected<dyn Component>> {
+        self.state.lock().unwrap().components.get(&type_id).cloned()
+    }
+
+    /// Keep lazy components from starting until the guard is dropped
+    pub(crate) async fn pause(&self) -> Guard<'_> {
+        Acquire { context: self }.await
+    }
+
+    /// Lazy components started from now on are post-started immediately
+    pub(crate) fn set_up(&self) {
+        self.state.lock().unwrap().up = true;
+    }
+
+    /// Drop all references to components, so that the system can stop them
+    pub(crate) fn close(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.closed = true;
+        state.components = HashMap::new();
+    }
+
+    /// Get a started singleton, starting it (and its dependencies) if it's lazy
+    pub(crate) async fn get(self: &Arc<Self>, type_id: TypeId) -> Injected<dyn Component> {
+        /* Started components don't have to wait for another lazy startup */
+        if let Some(component) = self.find(type_id) {
+            return component;
+        }
+
+        let _guard = Acquire { context: self }.await;
+
+        /* Started by the startup we've been waiting for */
+        if let Some(component) = self.find(type_id) {
+            return component;
+        }
+
+        let (up, started) = {
+            let state = self.state.lock().unwrap();
+            let started = self.started.upgrade();
+            if state.closed || started.is_none() {
+                panic!("Unable to start a lazy component of a stopped system");
+            }
+            (state.up, started.unwrap())
+        };
+
+        let mut plan = Vec::new();
+        self.plan(type_id, &mut plan, &mut HashSet::new());
+
+        /* Built by this startup, transient instances included */
+        let mut built = HashMap::new();
+
+        let mut last = None;
+        for tid in plan {
+            let meta = self.registry.get(tid).unwrap();
+
+            let starting = self.tracker.start(tid);
+            // The repository isn't Send, so it mustn't be held across an await
+            let mut component = {
+                let mut repository = ComponentRepository::new();
+                repository.insert_with_typeid(TypeId::of::<LazyContext>(), self.clone());
+                for (dep, c) in built.iter() {
+                    repository.insert_with_typeid(*dep, Injected::clone(c));
+                }
+                for dep in meta.depends_on.iter() {
+                    if let Some(c) = self.find(*dep) {
+                        repository.insert_with_typeid(*dep, c);
+                    }
+                }
+                (meta.build)(&repository)
+            };
+            component.start().await;
+            starting.done();
+            let component = Injected::from(component);
+            if up {
+                component.post_start().await;
+            }
+
+            started.lock().unwrap().push(StartedComponent {
+                type_id: tid,
+                name: meta.name,
+                component: component.clone()
+            });
+
+            // Transient instances are only visible to the next component, which depends on it
+            if meta.scope != ComponentScope::Transient {
+                self.insert(tid, component.clone());
+            }
+            built.insert(tid, component.clone());
+            last = Some(component);
+        }
+
+        last.unwrap()
+    }
+
+    /// Components that need to be built for `type_id`, dependencies first.
+    /// Like `System::start`, a transient component appears once per dependent.
+    fn plan(&self, type_id: TypeId, plan: &mut Vec<TypeId>, visiting: &mut HashSet<TypeId>) {
+        let meta = self.registry.get(type_id).unwrap_or_else(|| panic!(
+            "Unable to find the lazy component in the registry"
+        ));
+        let transient = meta.scope == ComponentScope::Transient;
+        if !transient && (self.find(type_id).is_some() || plan.contains(&type_id)) {
+            return;
+        }
+        if !visiting.insert(type_id) {
+            panic!("Unable to handle circular dependency in lazy components");
+        }
+
+        for dep in meta.depends_on.iter() {
+            self.plan(*dep, plan, visiting);
+        }
+
+        visiting.remove(&type_id);
+        plan.push(type_id);
+    }
+}
+
+/*
+ * Waits until no other lazy component is starting
+ */
+struct Acquire<'a> {
+    context: &'a LazyContext
+}
+
+pub(crate) struct Guard<'a> {
+    context: &'a LazyContext
+}
+
+impl<'a> Future for Acquire<'a> {
+    type Output = Guard<'a>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Guard<'a>> {
+        let mut state = self.context.state.lock().unwrap();
+        if state.starting {
+            state.waiters.push(cx.waker().clone());
+            return Poll::Pending;
+        }
+        state.starting = true;
+        Poll::Ready(Guard { context: self.context })
+    }
+}
+
+impl Drop for Guard<'_> {
+    fn drop(&mut self) {
+        let waiters = {
+            let mut state = self.context.state.lock().unwrap();
+            state.starting = false;
+            std::mem::take(&mut state.waiters)
+        };
+        for waker in waiters {
+            waker.wake();
+        }
+    }
+}
@@ -15,34 +15,87 @@ use std::any::{TypeId};
 /// Re-export from async_trait library
 pub use async_trait::async_trait;
 
-pub use sai_component_derive::Component;
+pub use sai_component_derive::{Component, provider};
 
 mod injected;
 pub use injected::Injected;
 
+mod dependency;
+pub use dependency::{Dependency, Constructor};
+
+mod provider;
+#[doc(hidden)]
+pub use provider::{provided, ProvidedValue, ProviderFuture, ProviderResult, Teardown};
+
 mod component_repository;
 #[doc(hidden)]
 pub use component_repository::ComponentRepository;
 
 mod system;
-pub use system::System;
+pub use system::{DEFAULT_STOP_TIMEOUT, DropPolicy, GetError, Leak, RestartError, SharedComponent, StopReport, System, SystemBuilder};
+
+mod scope;
+pub use scope::Scope;
+
+mod status;
+pub use status::{ComponentState, ComponentStatus};
+
+mod handle;
+pub use handle::SystemHandle;
+
+mod lazy;
+pub use lazy::Lazy;
+
+mod deferred;
+pub use deferred::Deferred;
+
+mod order;
+#[doc(hidden)]
+pub use order::{depth, Binds, Ordered, OrderedDependency};
+
+mod wire;
+#[doc(hidden)]
+pub use wire::{erase_wired, start_wired, stop_wired, Here, Pick, There, Wire, WiredDependency};
+
+mod executor;
 
 mod downcast;
 
 mod registry;
 #[doc(inline)]
-pub use registry::ComponentRegistry;
+pub use registry::{ComponentRegistry, DisabledComponent, Registrable, Registry, RegistryBuilder, RegistryConflict, ValidationError};
+#[doc(hidden)]
+pub use registry::{combine_metas, RegistryTable};
 
 /// ComponentLifecycle is simply start()/stop()
 ///
 /// You will have to manually implement this trait for your component if you want to have explict
 /// startup/shutdown logic.
 ///
+/// A System runs the hooks in phases:
+/// 1. `start` each component in the topological order, right after it's built
+/// 2. `post_start` each component in the same order, once every component has started
+/// 3. `pre_stop` each component in the reversed order, before any component stops
+/// 4. `stop` each component in the reversed order
+///
+/// E.g. an HTTP server should only accept traffic in `post_start`, and stop accepting it in
+/// `pre_stop`.
+///
 /// Check out the doc for [Component](trait.Component.html) trait
 #[async_trait()]
 pub trait ComponentLifecycle: Send { // Extend Send compiler stop complaining trait object issue
     async fn start(&mut self) {}
     async fn stop(&mut self) {}
+
+    /// Called once all components of the system have started
+    async fn post_start(&self) {}
+
+    /// Called before any component of the system stops
+    async fn pre_stop(&self) {}
+
+    /// Called instead of `stop` when the component is still referenced outside of the system
+    /// after the stop timeout, e.g. by a spawned task. See `System::with_stop_timeout`.
+    async fn stop_shared(&self) {}
 }
 
 /// A Component is a bunch of business-logic behaviors + startup logic.
@@ -85,20 +138,185 @@ pub trait ComponentLifecycle: Send { // Extend Send compiler stop complaining tr
 ///     }
 /// }
 /// ```
+///
+/// **Transient components**
+///
+/// By default, a component is a singleton: the system creates one instance and every dependent
+/// shares it. A component declared with `scope = "transient"` is created and started once for
+/// each dependent instead, and every instance is stopped individually when the system stops.
+/// ```
+/// use sai::{Component, Injected};
+/// #[derive(Component)]
+/// #[component(scope = "transient")]
+/// struct JobContext {}
+///
+/// #[derive(Component)]
+/// struct Worker {
+///     #[injected]
+///     context: Injected<JobContext> // Not shared with other dependents of JobContext
+/// }
+/// ```
+/// A transient component that no other component depends on is never created.
+///
+/// **Lazy components**
+///
+/// A component declared with `lazy` is not started by `System::start`. Inject it as
+/// `Lazy<Injected<T>>` instead, and it's built and started together with its dependencies on
+/// the first call to `get`.
+/// ```
+/// use sai::{Component, Injected, Lazy};
+/// #[derive(Component)]
+/// #[component(lazy)]
+/// struct ReportGenerator {}
+///
+/// #[derive(Component)]
+/// struct Admin {
+///     #[injected]
+///     reports: Lazy<Injected<ReportGenerator>>
+/// }
+/// ```
+/// A lazy component injected directly as `Injected<T>` into a component that is not lazy is
+/// started during `System::start` anyway.
+///
+/// **Profiles**
+///
+/// A component declared with `profile = "..."` is only part of a system that selects one of its
+/// profiles, see `System::with_profiles`. The attribute can be repeated. `#[sai::provider]`
+/// accepts it as well, so e.g. a `dev` and a `prod` provider can provide the same type.
+/// ```
+/// use sai::Component;
+/// #[derive(Component)]
+/// #[component(profile = "dev", profile = "test")]
+/// struct DebugConsole {}
+/// ```
+///
+/// **Conditional components**
+///
+/// A component declared with `enabled_if = "..."` is only part of a system whose configuration
+/// enables the key, see `System::with_config`. Components that depend on a disabled component
+/// fail validation.
+/// ```
+/// use sai::Component;
+/// #[derive(Component)]
+/// #[component(enabled_if = "features.search")]
+/// struct SearchIndex {}
+/// ```
+///
+/// **Custom constructors**
+///
+/// Fields that are not injected are initialised by `Default::default()`. When a field has to be
+/// computed from dependencies, point the derive at a constructor function instead. The
+/// parameters of the function are the dependencies of the component.
+/// ```
+/// use sai::{Component, Injected};
+/// #[derive(Component)]
+/// struct Config {
+///     endpoint: String
+/// }
+///
+/// #[derive(Component)]
+/// #[component(constructor = "Client::new")]
+/// struct Client {
+///     endpoint: String
+/// }
+///
+/// impl Client {
+///     fn new(config: Injected<Config>) -> Self {
+///         Client {
+///             endpoint: format!("{}/v1", config.endpoint)
+///         }
+///     }
+/// }
+/// ```
+/// `#[injected]` fields can't be used together with a constructor.
+///
+/// **Ordering-only dependencies**
+///
+/// A component that has to start after another one without using it, e.g. a server that
+/// requires database migrations to be done, can declare it with `after(...)`.
+/// ```
+/// use sai::Component;
+/// #[derive(Component)]
+/// struct Migrations {}
+///
+/// #[derive(Component)]
+/// #[component(after(Migrations))]
+/// struct HttpServer {}
+/// ```
+/// A generic component has to be named via a type alias there, e.g.
+/// `type UserRepo = Repo<User>;` and `after(UserRepo)`.
+///
+/// **Generic components**
+///
+/// Generic structs can be components as well. Every monomorphised type, e.g. `Repo<User>`, is
+/// a separate component and has to be registered on its own.
+/// ```
+/// use sai::{Component, Injected, component_registry};
+/// # #[derive(Component)]
+/// # struct Db {}
+/// # struct User {}
+/// #[derive(Component)]
+/// struct Repo<T> {
+///     #[injected]
+///     db: Injected<Db>,
+///     entity: std::marker::PhantomData<T>
+/// }
+///
+/// component_registry!(RootRegistry, [Db, Repo<User>]);
+/// ```
+///
+/// **Thread safety**
+///
+/// Components are shared across threads, so they have to be `Send + Sync`. Generic code
+/// bounded by `T: Component` may rely on it.
 #[async_trait()]
-pub trait Component: Send + downcast::Downcast + ComponentLifecycle {
+pub trait Component: Send + Sync + downcast::Downcast + ComponentLifecycle {
     fn build(registry: &ComponentRepository) -> Self
         where Self: Sized;
 
     fn meta() -> ComponentMeta<Box<Self>>
         where Self: Sized;
+
+    /// The value that dependents receive instead of the component, e.g. the value of a
+    /// `#[sai::provider]`, so that the System can tell whether it's still in use
+    #[doc(hidden)]
+    fn provided_value(&self) -> Option<ProvidedValue> {
+        None
+    }
+}
+
+/// How instances of a component are created by a System
+///
+/// Check out the doc for [Component](trait.Component.html) trait
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum ComponentScope {
+    /// Only one instance is created and shared by all dependents. This is the default.
+    #[default]
+    Singleton,
+    /// A new instance is created (and started) for each dependent.
+    Transient,
+    /// Only one instance is created, on first use via [Lazy](struct.Lazy.html) rather than
+    /// during `System::start`.
+    Lazy
 }
 
 #[doc(hidden)]
 pub struct ComponentMeta<T: ?Sized> {
     pub depends_on: Vec<TypeId>,
+    /*
+     * Everything that has to be registered for the component to be built, with its name.
+     * Unlike depends_on, it includes what `Lazy` and `Deferred` resolve on demand.
+     */
+    pub requires: Vec<(TypeId, &'static str)>,
     pub type_id: TypeId,
-    pub build: Box<dyn Fn(&ComponentRepository) -> T>
+    /* Used in diagnostics, e.g. the report of System::stop */
+    pub name: &'static str,
+    pub build: Box<dyn Fn(&ComponentRepository) -> T + Send + Sync>,
+    pub scope: ComponentScope,
+    /* The profiles in which the component is active, any profile if empty */
+    pub profiles: &'static [&'static str],
+    /* The configuration key that enables the component, see `Registry::with_config` */
+    pub enabled_if: Option<&'static str>
 }
 
 impl<T: Component + 'static> From<ComponentMeta<Box<T>>> for ComponentMeta<Box<dyn Component>> {
@@ -106,7 +324,12 @@ impl<T: Component + 'static> From<ComponentMeta<Box<T>>> for ComponentMeta<Box<d
     fn from(m: ComponentMeta<Box<T>>) -> Self {
         ComponentMeta {
             depends_on: m.depends_on.clone(),
+            requires: m.requires.clone(),
             type_id: m.type_id,
+            name: m.name,
+            scope: m.scope,
+            profiles: m.profiles,
+            enabled_if: m.enabled_if,
             build: Box::new(move |r: &ComponentRepository| (m.build)(r))
         }
     }
@@ -0,0 +1,57 @@
+use super::{Deferred, Injected, Lazy};
+
+/*
+ * The start order of a registry defined by `component_registry!(..., ordered)`, computed at
+ * compile time. The registry implements `Binds` for what each of its components is injected
+ * as, and every component implements `Ordered` for any registry that binds its dependencies.
+ *
+ * A missing dependency is an unsatisfied trait bound, and a circular dependency is a cycle
+ * in the evaluation of the constants, so both are compile errors.
+ */
+
+/// Implemented by an ordered registry for the dependency type of each of its components,
+/// e.g. `Injected<T>`
+#[doc(hidden)]
+pub trait Binds<D> {
+    /// The depth of the component bound to `D`
+    const DEPTH: usize;
+}
+
+/// Implemented by `#[derive(Component)]` and `#[sai::provider]`
+#[doc(hidden)]
+pub trait Ordered<R> {
+    /// The length of the longest chain of dependencies of the component in registry `R`
+    const DEPTH: usize;
+}
+
+#[doc(hidden)]
+pub trait OrderedDependency<R> {
+    const DEPTH: usize;
+}
+
+impl<R, T: ?Sized> OrderedDependency<R> for Injected<T> where R: Binds<Injected<T>> {
+    const DEPTH: usize = <R as Binds<Injected<T>>>::DEPTH + 1;
+}
+
+// Started on demand, so they only have to be registered
+impl<R, T: ?Sized> OrderedDependency<R> for Lazy<Injected<T>> where R: Binds<Injected<T>> {
+    const DEPTH: usize = 0;
+}
+
+impl<R, T: ?Sized> OrderedDependency<R> for Deferred<Injected<T>> where R: Binds<Injected<T>> {
+    const DEPTH: usize = 0;
+}
+
+/// The depth of a component with dependencies of the given depths
+#[doc(hidden)]
+pub const fn depth(dependencies: &[usize]) -> usize {
+    let mut depth = 0;
+    let mut i = 0;
+    while i < dependencies.len() {
+        if dependencies[i] > depth {
+            depth = dependencies[i];
+        }
+        i += 1;
+    }
+    depth
+}
@@ -0,0 +1,154 @@
+use std::any::Any;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Weak};
+use super::{Component, ComponentLifecycle, ComponentMeta, ComponentRepository, Injected, async_trait};
+
+#[doc(hidden)]
+pub type ProviderFuture<T> = Pin<Box<dyn Future<Output = Result<T, String>> + Send>>;
+
+#[doc(hidden)]
+pub type Teardown<T> = for<'a> fn(&'a T) -> Pin<Box<dyn Future<Output = ()> + Send + 'a>>;
+
+/// The return type of a fallible provider, i.e. a `Result` or an alias of it.
+///
+/// A provider returning a type named `Result` provides its first type argument. This trait
+/// makes it a compile error if that's not what the type returns on success, e.g. for
+/// `type Result<E> = std::result::Result<Pool, E>`.
+#[doc(hidden)]
+pub trait ProviderResult {
+    type Ok;
+    type Err: std::fmt::Debug;
+
+    fn into_result(self) -> Result<Self::Ok, Self::Err>;
+}
+
+impl<T, E: std::fmt::Debug> ProviderResult for Result<T, E> {
+    type Ok = T;
+    type Err = E;
+
+    fn into_result(self) -> Result<T, E> {
+        self
+    }
+}
+
+/// The component behind a `#[sai::provider]` function, see `Provided`
+#[doc(hidden)]
+pub fn provided<T, F>(name: &'static str, factory: F, teardown: Option<Teardown<T>>) -> Box<dyn Component>
+    where T: Send + Sync + 'static, F: FnOnce() -> ProviderFuture<T> + Send + Sync + 'static
+{
+    Box::new(Provided::new(name, factory, teardown))
+}
+
+/// The value of a provider or an instance, see `Component::provided_value`
+#[doc(hidden)]
+#[derive(Debug)]
+pub struct ProvidedValue {
+    value: Weak<dyn Any + Send + Sync>,
+    /* Set for instances, which the registry keeps so that the system can be started again */
+    retained_by: Option<Weak<dyn Any + Send + Sync>>
+}
+
+impl ProvidedValue {
+    /// References to the value other than the ones of its provider, if it's alive, and of the
+    /// registry
+    pub(crate) fn references(&self, provider_alive: bool) -> usize {
+        let retained = self.retained_by.as_ref().map(|r| r.strong_count().min(1)).unwrap_or(0);
+        self.value.strong_count().saturating_sub(provider_alive as usize + retained)
+    }
+}
+
+/*
+ * The component behind a `#[sai::provider]` function or an instance added by
+ * `SystemBuilder::instance`.
+ *
+ * It calls the provider function on start, and the teardown function (if any) on stop.
+ * Dependents receive the provided value as `Injected<T>`.
+ *
+ * It's private to the crate, so that it can't be registered on its own.
+ */
+pub(crate) struct Provided<T> {
+    name: &'static str,
+    factory: Option<Box<dyn FnOnce() -> ProviderFuture<T> + Send + Sync>>,
+    teardown: Option<Teardown<T>>,
+    value: Option<Injected<T>>,
+    retained_by: Option<Weak<Injected<T>>>
+}
+
+impl<T> Provided<T> {
+
+    fn new<F>(name: &'static str, factory: F, teardown: Option<Teardown<T>>) -> Self
+        where F: FnOnce() -> ProviderFuture<T> + Send + Sync + 'static
+    {
+        Provided {
+            name,
+            factory: Some(Box::new(factory)),
+            teardown,
+            value: None,
+            retained_by: None
+        }
+    }
+
+    /// An already existing value that `instance` keeps, see `SystemBuilder::instance`
+    pub(crate) fn ready(name: &'static str, instance: &Arc<Injected<T>>) -> Self {
+        Provided {
+            name,
+            factory: None,
+            teardown: None,
+            value: Some(Injected::clone(instance)),
+            retained_by: Some(Arc::downgrade(instance))
+        }
+    }
+
+    pub(crate) fn get(&self) -> Option<Injected<T>> {
+        self.value.clone()
+    }
+}
+
+#[async_trait]
+impl<T: Send + Sync + 'static> ComponentLifecycle for Provided<T> {
+    async fn start(&mut self) {
+        let factory = match self.factory.take() {
+            Some(f) => f,
+            None => return
+        };
+
+        match factory().await {
+            Ok(value) => self.value = Some(Injected::new(value)),
+            Err(e) => panic!("Provider {} failed: {}", self.name, e)
+        }
+    }
+
+    async fn stop(&mut self) {
+        if let (Some(teardown), Some(value)) = (self.teardown, self.value.as_ref()) {
+            teardown(value).await;
+        }
+        self.value = None;
+    }
+
+    async fn stop_shared(&self) {
+        if let (Some(teardown), Some(value)) = (self.teardown, self.value.as_ref()) {
+            teardown(value).await;
+        }
+    }
+}
+
+// Only ever built through `provided` or `Provided::ready`
+impl<T: Send + Sync + 'static> Component for Provided<T> {
+    fn build(_: &ComponentRepository) -> Self {
+        unreachable!("Provided components are built by their provider function")
+    }
+
+    fn meta() -> ComponentMeta<Box<Self>> {
+        unreachable!("Provided components are registered by their provider function")
+    }
+
+    fn provided_value(&self) -> Option<ProvidedValue> {
+        let value: Weak<T> = Arc::downgrade(self.value.as_ref()?);
+        let retained_by = self.retained_by.clone().map(|r| -> Weak<dyn Any + Send + Sync> { r });
+        Some(ProvidedValue {
+            value,
+            retained_by
+        })
+    }
+}
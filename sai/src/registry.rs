@@ -1,10 +1,13 @@
 use std::any::TypeId;
-use super::{ Component, ComponentMeta };
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use once_cell::sync::OnceCell;
+use super::{ Component, ComponentMeta, Injected };
 
 /// A macro that helps setting up Component Registry
 ///
 /// ```
-/// # use sai::{ComponentMeta, ComponentRepository, Component, ComponentLifecycle, component_registry, Injected};
+/// # use sai::{ComponentMeta, ComponentRepository, Component, ComponentLifecycle, ComponentScope, component_registry, Injected};
 /// # use std::any::TypeId;
 /// # struct A { }
 /// # impl Component for A {
@@ -13,53 +16,126 @@ use super::{ Component, ComponentMeta };
 /// #     fn meta() -> ComponentMeta<Box<A>> {
 /// #         ComponentMeta {
 /// #             type_id: TypeId::of::<Injected<A>>(),
+/// #             name: "A",
 /// #             build: Box::new(|_| Box::new(A{})),
-/// #             depends_on: vec![ ]
+/// #             depends_on: vec![ ],
+/// #             requires: vec![ ],
+/// #             scope: ComponentScope::Singleton,
+/// #             profiles: &[],
+/// #             enabled_if: None
 /// #         }
 /// #     }
 /// # }
 /// # impl ComponentLifecycle for A {}
 /// component_registry!(DummyRegistry, [A]);
 /// ```
+///
+/// With the `ordered` option, the order in which a System starts the components is computed at
+/// compile time, and a missing or circular dependency is a compile error rather than a panic
+/// in `System::start`:
+///
+/// ```ignore
+/// component_registry!(RootRegistry, [Db, UserRepo, HttpServer], ordered);
+/// ```
+///
+/// A circular dependency is reported by the compiler as a cycle in evaluating the constant
+/// `DEPTH` of the components involved (error E0391):
+///
+/// ```compile_fail,E0391
+/// use sai::{Component, Injected, component_registry};
+///
+/// #[derive(Component)]
+/// struct Ping {
+///     #[injected]
+///     pong: Injected<Pong>
+/// }
+///
+/// #[derive(Component)]
+/// struct Pong {
+///     #[injected]
+///     ping: Injected<Ping>
+/// }
+///
+/// component_registry!(RootRegistry, [Ping, Pong], ordered);
+/// ```
+///
+/// An ordered registry can't contain components with a custom constructor, nor two
+/// components or providers of the same type in different profiles.
 #[macro_export]
 macro_rules! component_registry {
     ($name:ident, [$($x:ty),*]) => {
+        $crate::component_registry!(@registry $name, [$($x),*], None);
+    };
 
-        pub struct $name {}
-
-        impl $crate::ComponentRegistry for $name {
-            fn get (tid: std::any::TypeId) -> Option<$crate::ComponentMeta<Box<dyn $crate::Component>>> {
+    ($name:ident, [$($x:ty),*], ordered) => {
+        $crate::component_registry!(@registry $name, [$($x),*], Some({
+            // Sorting by depth keeps every component after its dependencies
+            let mut order = vec![
                 $(
-                    let meta = <$x>::meta();
-                    if tid == meta.type_id {
-                        return Some(meta.into())
-                    }
+                    (
+                        <$x as $crate::Ordered<$name>>::DEPTH,
+                        <$x as $crate::Registrable>::component_type_id()
+                    ),
                 )*
+            ];
+            order.sort_by_key(|(depth, _)| *depth);
+            order.into_iter().map(|(_, tid)| tid).collect()
+        }));
 
-                None
-
+        $(
+            impl $crate::Binds<<$x as $crate::Registrable>::Binding> for $name {
+                const DEPTH: usize = <$x as $crate::Ordered<$name>>::DEPTH;
             }
 
-            fn all () -> Vec<std::any::TypeId> {
-                vec![
+            // Report missing and circular dependencies even if the order is never used
+            const _: usize = <$x as $crate::Ordered<$name>>::DEPTH;
+        )*
+    };
+
+    (@registry $name:ident, [$($x:ty),*], $order:expr) => {
+
+        pub struct $name {}
+
+        impl $name {
+            fn __table () -> &'static $crate::RegistryTable {
+                static TABLE: $crate::RegistryTable = $crate::RegistryTable::new();
+                TABLE.init(|| vec![
                     $(
-                        std::any::TypeId::of::<$crate::Injected<$x>>(),
+                        <$x as $crate::Registrable>::component_meta(),
                     )*
-                ]
+                ])
+            }
+        }
+
+        impl $crate::ComponentRegistry for $name {
+            fn get (tid: std::any::TypeId) -> Option<&'static $crate::ComponentMeta<Box<dyn $crate::Component>>> {
+                $name::__table().get(tid)
+            }
+
+            fn all () -> Vec<std::any::TypeId> {
+                $name::__table().all()
             }
 
             fn new () -> Self {
                 $name{}
             }
+
+            fn metas () -> Vec<&'static $crate::ComponentMeta<Box<dyn $crate::Component>>> {
+                $name::__table().metas().iter().collect()
+            }
+
+            fn order () -> Option<Vec<std::any::TypeId>> {
+                $order
+            }
         }
 
-    }
+    };
 }
 
 /// A macro that combines any number of Component Registry
 ///
 /// ```
-/// # use sai::{ComponentMeta, ComponentRepository, Component, ComponentLifecycle, component_registry, Injected, combine_component_registry};
+/// # use sai::{ComponentMeta, ComponentRepository, Component, ComponentLifecycle, ComponentScope, component_registry, Injected, combine_component_registry};
 /// # use std::any::TypeId;
 /// # struct A { }
 /// # impl Component for A {
@@ -68,8 +144,13 @@ macro_rules! component_registry {
 /// #     fn meta() -> ComponentMeta<Box<A>> {
 /// #         ComponentMeta {
 /// #             type_id: TypeId::of::<Injected<A>>(),
+/// #             name: "A",
 /// #             build: Box::new(|_| Box::new(A{})),
-/// #             depends_on: vec![ ]
+/// #             depends_on: vec![ ],
+/// #             requires: vec![ ],
+/// #             scope: ComponentScope::Singleton,
+/// #             profiles: &[],
+/// #             enabled_if: None
 /// #         }
 /// #     }
 /// # }
@@ -80,39 +161,232 @@ macro_rules! component_registry {
 /// // Combine DummyRegistry and DummyRegistry2 into SuperRegistry
 /// combine_component_registry!(SuperRegistry, [ DummyRegistry, DummyRegistry2 ]);
 /// ```
+///
+/// A component listed by several registries is only registered once. But two registries
+/// binding the same type to different components or providers, e.g. a `Box<dyn Cache>`,
+/// is a conflict and panics when the registry is used, unless their profiles are disjoint.
+/// Declare the registries that win such conflicts with `overrides`:
+///
+/// ```ignore
+/// combine_component_registry!(TestRegistry, [ AppRegistry ], overrides = [ FakesRegistry ]);
+/// ```
+///
+/// Conflicts can be checked without using the registry, e.g. in a test:
+///
+/// ```ignore
+/// #[test]
+/// fn no_conflicts() {
+///     assert_eq!(TestRegistry::validate(), Ok(()));
+/// }
+/// ```
 #[macro_export]
 macro_rules! combine_component_registry {
-    ($name:ident, [$($x:ty),*]) => {
+    ($name:ident, [$($x:ty),*] $(, overrides = [$($o:ty),*])?) => {
 
         pub struct $name {}
 
-        impl $crate::ComponentRegistry for $name {
-            fn get (tid: std::any::TypeId) -> Option<$crate::ComponentMeta<Box<dyn $crate::Component>>> {
-                $(
-                    let meta = <$x>::get(tid);
-                    if meta.is_some() {
-                        return meta;
-                    }
-                )*
+        impl $name {
+            /// Check that the combined registries don't bind a type to different components
+            #[allow(dead_code)]
+            pub fn validate () -> Result<(), $crate::RegistryConflict> {
+                $name::__combine().map(|_| ())
+            }
 
-                None
+            fn __combine () -> Result<Vec<$crate::ComponentMeta<Box<dyn $crate::Component>>>, $crate::RegistryConflict> {
+                $crate::combine_metas(
+                    vec![ $((stringify!($x), <$x as $crate::ComponentRegistry>::metas())),* ],
+                    vec![ $($((stringify!($o), <$o as $crate::ComponentRegistry>::metas())),*)? ]
+                )
+            }
 
+            fn __table () -> &'static $crate::RegistryTable {
+                static TABLE: $crate::RegistryTable = $crate::RegistryTable::new();
+                TABLE.init(|| $name::__combine().unwrap_or_else(|conflict| panic!("{}", conflict)))
+            }
+        }
+
+        impl $crate::ComponentRegistry for $name {
+            fn get (tid: std::any::TypeId) -> Option<&'static $crate::ComponentMeta<Box<dyn $crate::Component>>> {
+                $name::__table().get(tid)
             }
 
             fn all () -> Vec<std::any::TypeId> {
-                let mut result = Vec::new();
-                $(
-                    let mut all = <$x>::all();
-                    result.append(&mut all);
-                )*
-                return result;
+                $name::__table().all()
             }
 
             fn new () -> Self {
                 $name{}
             }
+
+            fn metas () -> Vec<&'static $crate::ComponentMeta<Box<dyn $crate::Component>>> {
+                $name::__table().metas().iter().collect()
+            }
+        }
+
+    }
+}
+
+/* The metas of each registry, with the name of the registry */
+type NamedMetas = Vec<(&'static str, Vec<&'static ComponentMeta<Box<dyn Component>>>)>;
+
+/// Two registries combined by `combine_component_registry!` bind the same type to different
+/// components, in overlapping profiles
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RegistryConflict {
+    pub component: &'static str,
+    pub registry: &'static str,
+    pub other_component: &'static str,
+    pub other_registry: &'static str
+}
+
+impl fmt::Display for RegistryConflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Conflicting registrations: {} in {} and {} in {} provide the same type. \
+             Declare the one that wins with `overrides = [...]` in combine_component_registry!",
+            self.other_component, self.other_registry, self.component, self.registry
+        )
+    }
+}
+
+impl std::error::Error for RegistryConflict {}
+
+/*
+ * The metas of a combined registry, see `combine_component_registry!`.
+ */
+#[doc(hidden)]
+pub fn combine_metas(
+    registries: NamedMetas,
+    overrides: NamedMetas
+) -> Result<Vec<ComponentMeta<Box<dyn Component>>>, RegistryConflict> {
+    let overridden: Vec<TypeId> = overrides
+        .iter()
+        .flat_map(|(_, metas)| metas.iter().map(|meta| meta.type_id))
+        .collect();
+
+    let mut combined = Vec::new();
+    for (registry, metas) in registries {
+        for meta in metas.into_iter().filter(|meta| !overridden.contains(&meta.type_id)) {
+            combine_meta(&mut combined, registry, meta)?;
         }
+    }
+    for (registry, metas) in overrides {
+        for meta in metas {
+            combine_meta(&mut combined, registry, meta)?;
+        }
+    }
+
+    Ok(combined.into_iter().map(|(_, meta)| share(meta)).collect())
+}
 
+fn combine_meta(
+    combined: &mut Vec<(&'static str, &'static ComponentMeta<Box<dyn Component>>)>,
+    registry: &'static str,
+    meta: &'static ComponentMeta<Box<dyn Component>>
+) -> Result<(), RegistryConflict> {
+    let bound = combined.iter().filter(|(_, other)| other.type_id == meta.type_id);
+    for &(other_registry, other) in bound {
+        if other.name == meta.name {
+            return Ok(());
+        }
+        if profiles_overlap(other.profiles, meta.profiles) {
+            return Err(RegistryConflict {
+                component: meta.name,
+                registry,
+                other_component: other.name,
+                other_registry
+            });
+        }
+    }
+    combined.push((registry, meta));
+    Ok(())
+}
+
+/* Whether a selection of profiles can activate both */
+fn profiles_overlap(a: &[&str], b: &[&str]) -> bool {
+    a.is_empty() || b.is_empty() || a.iter().any(|p| b.contains(p))
+}
+
+/*
+ * The table behind a registry defined by `component_registry!` or `combine_component_registry!`.
+ * Every component's meta() is called once, on first use, and the table lives for the rest of
+ * the program. Lookups are a binary search over the TypeIds, and return references into it.
+ *
+ * A System copies the metas it uses into its Registry once, when it's created.
+ */
+#[doc(hidden)]
+#[derive(Default)]
+pub struct RegistryTable {
+    table: OnceCell<Table>
+}
+
+struct Table {
+    metas: Vec<ComponentMeta<Box<dyn Component>>>,
+    /* The TypeIds in the order they were registered, without duplicates */
+    type_ids: Vec<TypeId>,
+    /* Sorted by TypeId, pointing to the first meta of each TypeId */
+    index: Vec<(TypeId, usize)>
+}
+
+/* A meta that builds the component with a static one, e.g. one in a RegistryTable */
+fn share(meta: &'static ComponentMeta<Box<dyn Component>>) -> ComponentMeta<Box<dyn Component>> {
+    ComponentMeta {
+        depends_on: meta.depends_on.clone(),
+        requires: meta.requires.clone(),
+        type_id: meta.type_id,
+        name: meta.name,
+        build: Box::new(move |r| (meta.build)(r)),
+        scope: meta.scope,
+        profiles: meta.profiles,
+        enabled_if: meta.enabled_if
+    }
+}
+
+impl RegistryTable {
+
+    pub const fn new() -> Self {
+        RegistryTable {
+            table: OnceCell::new()
+        }
+    }
+
+    /// Build the table with `metas` unless it's built already
+    pub fn init<F>(&'static self, metas: F) -> &'static Self
+        where F: FnOnce() -> Vec<ComponentMeta<Box<dyn Component>>>
+    {
+        self.table.get_or_init(|| {
+            let metas = metas();
+            let mut type_ids = Vec::new();
+            let mut index = Vec::new();
+            for (i, meta) in metas.iter().enumerate() {
+                if !type_ids.contains(&meta.type_id) {
+                    type_ids.push(meta.type_id);
+                    index.push((meta.type_id, i));
+                }
+            }
+            index.sort();
+            Table { metas, type_ids, index }
+        });
+        self
+    }
+
+    pub fn get(&'static self, type_id: TypeId) -> Option<&'static ComponentMeta<Box<dyn Component>>> {
+        let table = self.table();
+        let i = table.index.binary_search_by_key(&type_id, |(tid, _)| *tid).ok()?;
+        Some(&table.metas[table.index[i].1])
+    }
+
+    pub fn all(&'static self) -> Vec<TypeId> {
+        self.table().type_ids.clone()
+    }
+
+    pub fn metas(&'static self) -> &'static [ComponentMeta<Box<dyn Component>>] {
+        &self.table().metas
+    }
+
+    fn table(&'static self) -> &'static Table {
+        self.table.get().expect("RegistryTable is used before init")
     }
 }
 
@@ -139,16 +413,567 @@ macro_rules! combine_component_registry {
 /// ```
 /// Note that A, B above are not values, they are the identifiers.
 ///
+/// The meta information of the components is built once, on first use, into a static table
+/// sorted by TypeId, and `get` and `metas` hand out references to it. A System copies it into a
+/// [Registry](struct.Registry.html) when it's created and only uses that for the rest of its run.
+///
 /// In big project, uou can also composite multiple component registires into one.
 /// Check out [here](macro.combine_component_registry.html).
+///
+/// If the components can only be decided at runtime, use a [RegistryBuilder](struct.RegistryBuilder.html) instead.
 pub trait ComponentRegistry {
-    /// Getting a
-    fn get (type_id: TypeId) -> Option<ComponentMeta<Box<dyn Component>>>;
+    /// Getting the meta information of a component, regardless of its profiles.
+    /// If several components share the TypeId, the first one registered is returned.
+    fn get (type_id: TypeId) -> Option<&'static ComponentMeta<Box<dyn Component>>>;
 
-    /// All the TypeIds that's in this registry
+    /// All the TypeIds that's in this registry, once each, regardless of profiles
     fn all () -> Vec<TypeId>;
 
     fn new () -> Self;
+
+    /// Meta information of everything in this registry, including components of different
+    /// profiles that share a TypeId, e.g. an in-memory cache for `dev` and a Redis backed one
+    /// for `prod`. See `Registry::with_profiles`.
+    fn metas () -> Vec<&'static ComponentMeta<Box<dyn Component>>> {
+        Self::all()
+            .into_iter()
+            .filter_map(Self::get)
+            .collect()
+    }
+
+    /// The start order computed at compile time, see `component_registry!(..., ordered)`
+    fn order () -> Option<Vec<TypeId>> {
+        None
+    }
+
+    /// The TypeIds of the components that are active in the selected profiles
+    fn all_in_profiles (profiles: &[&str]) -> Vec<TypeId> {
+        let mut type_ids = Vec::new();
+        for meta in Self::metas().iter().filter(|meta| in_profiles(meta.profiles, profiles)) {
+            if !type_ids.contains(&meta.type_id) {
+                type_ids.push(meta.type_id);
+            }
+        }
+        type_ids
+    }
+
+    /// The meta information of the component that is active in the selected profiles.
+    /// Like in a [Registry](struct.Registry.html), the last one registered wins.
+    fn get_in_profiles (type_id: TypeId, profiles: &[&str]) -> Option<&'static ComponentMeta<Box<dyn Component>>> {
+        Self::metas()
+            .into_iter()
+            .rev()
+            .find(|meta| meta.type_id == type_id && in_profiles(meta.profiles, profiles))
+    }
+}
+
+/* Whether a component of the given profiles is active when `selected` are */
+fn in_profiles<S: AsRef<str>>(profiles: &[&str], selected: &[S]) -> bool {
+    profiles.is_empty() || profiles.iter().any(|p| selected.iter().any(|s| s.as_ref() == *p))
+}
+
+/// Anything that can be put in a registry: a [Component](trait.Component.html), or a provider
+/// function annotated with `#[sai::provider]`.
+pub trait Registrable {
+    /// What dependents inject, i.e. `Injected<T>`
+    type Binding;
+
+    /// The TypeId that dependents use to refer to it, i.e. `TypeId::of::<Injected<T>>()`
+    fn component_type_id() -> TypeId;
+
+    fn component_meta() -> ComponentMeta<Box<dyn Component>>;
+}
+
+impl<T: Component + 'static> Registrable for T {
+    type Binding = Injected<T>;
+
+    fn component_type_id() -> TypeId {
+        TypeId::of::<Injected<T>>()
+    }
+
+    fn component_meta() -> ComponentMeta<Box<dyn Component>> {
+        T::meta().into()
+    }
+}
+
+/// Registry is a value-level counterpart of [ComponentRegistry](trait.ComponentRegistry.html).
+///
+/// Unlike a registry defined by `component_registry!`, its content is decided at runtime,
+/// which makes it possible to include components conditionally.
+/// A Registry is created by a [RegistryBuilder](struct.RegistryBuilder.html) and can be run by
+/// a System via `System::from_registry`.
+///
+/// ```
+/// use sai::{Component, Registry, System};
+///
+/// #[derive(Component)]
+/// struct Db {}
+///
+/// #[derive(Component)]
+/// struct Cache {}
+///
+/// let use_cache = std::env::var("USE_CACHE").is_ok();
+///
+/// let registry = Registry::builder()
+///     .register::<Db>()
+///     .register_if::<Cache>(use_cache)
+///     .build();
+///
+/// let system = System::from_registry(registry);
+/// ```
+///
+/// Components declared with `#[component(profile = "...")]` are only part of the registry when
+/// one of their profiles is selected with `with_profiles`. Components without a profile are
+/// always part of it.
+/// ```
+/// use sai::{Registry, component_registry};
+/// # use sai::Component;
+/// # #[derive(Component)]
+/// # struct Db {}
+/// # #[derive(Component)]
+/// # #[component(profile = "dev")]
+/// # struct MemoryCache {}
+/// # #[derive(Component)]
+/// # #[component(profile = "prod")]
+/// # struct RedisCache {}
+///
+/// component_registry!(RootRegistry, [Db, MemoryCache, RedisCache]);
+///
+/// let registry = Registry::of::<RootRegistry>().with_profiles(&["prod"]);
+/// assert_eq!(registry.len(), 2);
+/// ```
+///
+/// Similarly, components declared with `#[component(enabled_if = "...")]` are only part of the
+/// registry when their configuration key is enabled, see `with_config`.
+#[derive(Default)]
+pub struct Registry {
+    type_ids: Vec<TypeId>,
+
+    /*
+     * Every meta registered for a TypeId, the last active one wins
+     */
+    metas: HashMap<TypeId, Vec<ComponentMeta<Box<dyn Component>>>>,
+
+    profiles: Vec<String>,
+
+    /*
+     * Evaluated `enabled_if` keys, unknown keys are disabled
+     */
+    config: HashMap<&'static str, bool>,
+
+    /*
+     * The start order computed at compile time, if any
+     */
+    order: Option<Vec<TypeId>>,
+
+    /*
+     * The index of the active meta of each TypeId, kept up to date with the profiles and the
+     * configuration so that lookups by a running System don't have to evaluate them
+     */
+    active: HashMap<TypeId, usize>
+}
+
+impl Registry {
+
+    /// Create an empty registry builder
+    pub fn builder() -> RegistryBuilder {
+        RegistryBuilder::new()
+    }
+
+    /// Create a Registry containing everything in a static ComponentRegistry
+    pub fn of<T: ComponentRegistry>() -> Self {
+        RegistryBuilder::new()
+            .registry::<T>()
+            .build()
+    }
+
+    /// Select the active profiles, replacing the previous selection
+    pub fn with_profiles(mut self, profiles: &[&str]) -> Self {
+        self.set_profiles(profiles);
+        self
+    }
+
+    pub(crate) fn set_profiles(&mut self, profiles: &[&str]) {
+        self.profiles = profiles.iter().map(|p| p.to_string()).collect();
+        self.refresh();
+    }
+
+    /// The active profiles
+    pub fn profiles(&self) -> &[String] {
+        &self.profiles
+    }
+
+    /// Evaluate the `enabled_if` keys of the registered components with a configuration lookup,
+    /// e.g. backed by a config file or environment variables.
+    ///
+    /// ```ignore
+    /// let registry = Registry::of::<RootRegistry>()
+    ///     .with_config(|key| settings.get_bool(key).unwrap_or(false));
+    /// ```
+    ///
+    /// A component whose key is never evaluated is disabled.
+    pub fn with_config<F: Fn(&str) -> bool>(mut self, config: F) -> Self {
+        self.set_config(&config);
+        self
+    }
+
+    pub(crate) fn set_config(&mut self, config: &dyn Fn(&str) -> bool) {
+        let keys: Vec<&'static str> = self.metas
+            .values()
+            .flatten()
+            .filter_map(|meta| meta.enabled_if)
+            .collect();
+        self.config = keys.into_iter().map(|key| (key, config(key))).collect();
+        self.refresh();
+    }
+
+    /// Components in the selected profiles that are disabled by the configuration
+    pub fn disabled(&self) -> Vec<DisabledComponent> {
+        self.type_ids
+            .iter()
+            .filter(|tid| !self.contains(**tid))
+            .filter_map(|tid| {
+                self.metas[tid]
+                    .iter()
+                    .rev()
+                    .find(|meta| self.in_profiles(meta))
+                    .map(|meta| DisabledComponent {
+                        name: meta.name,
+                        condition: meta.enabled_if.unwrap()
+                    })
+            })
+            .collect()
+    }
+
+    /// Getting the meta information of an active component
+    pub fn get(&self, type_id: TypeId) -> Option<&ComponentMeta<Box<dyn Component>>> {
+        let i = self.active.get(&type_id)?;
+        Some(&self.metas[&type_id][*i])
+    }
+
+    /// All the TypeIds of active components, in the order they were registered
+    pub fn all(&self) -> Vec<TypeId> {
+        self.type_ids
+            .iter()
+            .filter(|tid| self.contains(**tid))
+            .cloned()
+            .collect()
+    }
+
+    /// The start order computed at compile time, see `component_registry!(..., ordered)`.
+    /// None if a component registered later has to start before one of its dependencies.
+    pub(crate) fn order(&self) -> Option<&[TypeId]> {
+        let order = self.order.as_deref()?;
+        let mut started = HashSet::new();
+        for tid in order {
+            if let Some(meta) = self.get(*tid) {
+                if meta.depends_on.iter().any(|dep| self.contains(*dep) && !started.contains(dep)) {
+                    return None;
+                }
+            }
+            started.insert(*tid);
+        }
+        Some(order)
+    }
+
+    pub fn contains(&self, type_id: TypeId) -> bool {
+        self.get(type_id).is_some()
+    }
+
+    pub fn len(&self) -> usize {
+        self.all().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Check that every dependency of an active component is an active component as well,
+    /// including the ones resolved on demand by `Lazy` and `Deferred`
+    pub fn validate(&self) -> Result<(), Vec<ValidationError>> {
+        self.validate_with(None)
+    }
+
+    /// Like `validate`, for a scoped registry whose components can depend on the components of
+    /// the system as well
+    pub(crate) fn validate_scoped(&self, parent: &Registry) -> Result<(), Vec<ValidationError>> {
+        self.validate_with(Some(parent))
+    }
+
+    fn validate_with(&self, parent: Option<&Registry>) -> Result<(), Vec<ValidationError>> {
+        let mut errors = Vec::new();
+        for tid in self.all() {
+            let meta = self.get(tid).unwrap();
+            for &(dep, dependency) in meta.requires.iter() {
+                if self.contains(dep) || parent.map(|p| p.contains(dep)).unwrap_or(false) {
+                    continue;
+                }
+                // Explained by whichever registry has the dependency
+                let registry = match parent {
+                    Some(p) if !self.metas.contains_key(&dep) => p,
+                    _ => self
+                };
+                errors.push(registry.unsatisfied(meta.name, dep, dependency));
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
+    /* Why `dependency` of `component` is not active in this registry */
+    fn unsatisfied(&self, component: &'static str, dep: TypeId, dependency: &'static str) -> ValidationError {
+        let candidates = self.metas.get(&dep).map(Vec::as_slice).unwrap_or(&[]);
+        let disabled = candidates.iter().rev().find(|d| self.in_profiles(d));
+        match (disabled, candidates.last()) {
+            // Disabled by the configuration
+            (Some(d), _) => ValidationError::Disabled {
+                component,
+                dependency: d.name,
+                condition: d.enabled_if.unwrap()
+            },
+            // Excluded by the selected profiles
+            (None, Some(d)) => ValidationError::Inactive {
+                component,
+                dependency: d.name,
+                profiles: d.profiles
+            },
+            (None, None) => ValidationError::Missing { component, dependency }
+        }
+    }
+
+    fn is_active(&self, meta: &ComponentMeta<Box<dyn Component>>) -> bool {
+        self.in_profiles(meta) && self.is_enabled(meta)
+    }
+
+    fn in_profiles(&self, meta: &ComponentMeta<Box<dyn Component>>) -> bool {
+        in_profiles(meta.profiles, &self.profiles)
+    }
+
+    fn is_enabled(&self, meta: &ComponentMeta<Box<dyn Component>>) -> bool {
+        match meta.enabled_if {
+            Some(key) => self.config.get(key).cloned().unwrap_or(false),
+            None => true
+        }
+    }
+
+    fn insert(&mut self, meta: ComponentMeta<Box<dyn Component>>) {
+        let type_id = meta.type_id;
+        let active = self.is_active(&meta);
+        let metas = self.metas.entry(type_id).or_default();
+        if metas.is_empty() {
+            self.type_ids.push(type_id);
+            // Started after the ordered components, see `order`
+            if let Some(order) = self.order.as_mut() {
+                if !order.contains(&type_id) {
+                    order.push(type_id);
+                }
+            }
+        }
+        metas.push(meta);
+        if active {
+            self.active.insert(type_id, metas.len() - 1);
+        }
+    }
+
+    /* Keep the compile-time order as long as everything registered before is ordered too */
+    fn extend_order(&mut self, order: Option<Vec<TypeId>>) {
+        let empty = self.type_ids.is_empty();
+        match (&mut self.order, order) {
+            (Some(current), Some(order)) => {
+                for tid in order {
+                    if !current.contains(&tid) {
+                        current.push(tid);
+                    }
+                }
+            },
+            (current, Some(order)) if empty => *current = Some(order),
+            _ => {}
+        }
+    }
+
+    /* The last active meta of each TypeId wins */
+    fn refresh(&mut self) {
+        let active: HashMap<TypeId, usize> = self.metas
+            .iter()
+            .filter_map(|(tid, metas)| {
+                metas.iter().rposition(|meta| self.is_active(meta)).map(|i| (*tid, i))
+            })
+            .collect();
+        self.active = active;
+    }
+}
+
+/* A configuration lookup for `enabled_if` keys, see `Registry::with_config` */
+pub(crate) type Config = Box<dyn Fn(&str) -> bool>;
+
+/// A problem found by `Registry::validate`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ValidationError {
+    /// A component depends on something that is not registered
+    Missing {
+        component: &'static str,
+        dependency: &'static str
+    },
+    /// A component depends on a component that is not in any of the selected profiles
+    Inactive {
+        component: &'static str,
+        dependency: &'static str,
+        profiles: &'static [&'static str]
+    },
+    /// A component depends on a component that is disabled by the configuration
+    Disabled {
+        component: &'static str,
+        dependency: &'static str,
+        condition: &'static str
+    }
+}
+
+/// A component that is disabled by its `enabled_if` configuration key, see `Registry::disabled`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DisabledComponent {
+    pub name: &'static str,
+    pub condition: &'static str
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ValidationError::Missing { component, dependency } => write!(
+                f,
+                "{} depends on {}, which is not registered",
+                component, dependency
+            ),
+            ValidationError::Inactive { component, dependency, profiles } => write!(
+                f,
+                "{} depends on {}, which is only active in profiles {:?}",
+                component, dependency, profiles
+            ),
+            ValidationError::Disabled { component, dependency, condition } => write!(
+                f,
+                "{} depends on {}, which is disabled because `{}` is not enabled",
+                component, dependency, condition
+            )
+        }
+    }
+}
+
+impl std::error::Error for ValidationError {}
+
+/// A builder for [Registry](struct.Registry.html).
+///
+/// Components can be registered one-by-one, conditionally, or in bulk from an existing
+/// `ComponentRegistry`. Registering a component that is already in the builder replaces its
+/// meta information but keeps its original position.
+///
+/// ```
+/// use sai::{Component, RegistryBuilder, component_registry};
+///
+/// #[derive(Component)]
+/// struct A {}
+/// #[derive(Component)]
+/// struct B {}
+///
+/// component_registry!(CoreRegistry, [A]);
+///
+/// let registry = RegistryBuilder::new()
+///     .registry::<CoreRegistry>()
+///     .merge(RegistryBuilder::new().register::<B>())
+///     .build();
+///
+/// assert_eq!(registry.len(), 2);
+/// ```
+#[derive(Default)]
+pub struct RegistryBuilder {
+    registry: Registry,
+    config: Option<Config>
+}
+
+impl RegistryBuilder {
+
+    pub fn new() -> Self {
+        RegistryBuilder::default()
+    }
+
+    /// Add a component (or a provider) to the registry
+    pub fn register<C: Registrable>(mut self) -> Self {
+        self.registry.insert(C::component_meta());
+        self
+    }
+
+    /// Add a component (or a provider) to the registry only if `condition` is true
+    pub fn register_if<C: Registrable>(self, condition: bool) -> Self {
+        if condition {
+            self.register::<C>()
+        } else {
+            self
+        }
+    }
+
+    pub(crate) fn register_meta(mut self, meta: ComponentMeta<Box<dyn Component>>) -> Self {
+        self.registry.insert(meta);
+        self
+    }
+
+    /// Add all components of a static ComponentRegistry, e.g. one defined by `component_registry!`.
+    ///
+    /// The start order of an `ordered` registry is kept if it's added first or after other
+    /// ordered ones. Components registered later start after the ordered ones, unless one of
+    /// the ordered components depends on them; then the System orders them all at runtime.
+    pub fn registry<T: ComponentRegistry>(mut self) -> Self {
+        self.registry.extend_order(T::order());
+        for meta in T::metas() {
+            self.registry.insert(share(meta));
+        }
+        self
+    }
+
+    /// Add all components of another builder, together with its start order (see `registry`),
+    /// its selected profiles and its configuration lookup, which replaces this one's
+    pub fn merge(mut self, other: RegistryBuilder) -> Self {
+        let Registry { type_ids, mut metas, profiles, order, .. } = other.registry;
+        if other.config.is_some() {
+            self.config = other.config;
+        }
+        self.registry.extend_order(order);
+        let mut selected = self.registry.profiles.clone();
+        for profile in profiles {
+            if !selected.contains(&profile) {
+                selected.push(profile);
+            }
+        }
+        let selected: Vec<&str> = selected.iter().map(String::as_str).collect();
+        self.registry.set_profiles(&selected);
+        for tid in type_ids {
+            for meta in metas.remove(&tid).unwrap_or_default() {
+                self.registry.insert(meta);
+            }
+        }
+        self
+    }
+
+    /// Select the active profiles, see `Registry::with_profiles`
+    pub fn profiles(mut self, profiles: &[&str]) -> Self {
+        self.registry.set_profiles(profiles);
+        self
+    }
+
+    /// Evaluate `enabled_if` keys with a configuration lookup, see `Registry::with_config`.
+    /// The keys are evaluated by `build`, so components can be registered in any order.
+    pub fn config<F: Fn(&str) -> bool + 'static>(mut self, config: F) -> Self {
+        self.config = Some(Box::new(config));
+        self
+    }
+
+    pub fn build(mut self) -> Registry {
+        if let Some(config) = self.config {
+            self.registry.set_config(&config);
+        }
+        self.registry
+    }
 }
 
 
@@ -167,8 +992,13 @@ mod tests {
         fn meta() -> ComponentMeta<Box<A>> {
             ComponentMeta {
                 type_id: TypeId::of::<Injected<A>>(),
+                name: "A",
                 build: Box::new(|_| Box::new(A{})),
-                depends_on: vec![ ]
+                depends_on: vec![ ],
+                requires: vec![ ],
+                scope: ComponentScope::Singleton,
+                profiles: &[],
+                enabled_if: None
             }
         }
     }
@@ -190,6 +1020,168 @@ mod tests {
     fn combine_registries_new_macro() {
         assert!(matches!(CombinedRegistry::get(TypeId::of::<i32>()), None));
         assert!(matches!(CombinedRegistry::get(TypeId::of::<Injected<A>>()), Some(_)));
+
+        // A is listed by both registries, but only registered once
+        assert_eq!(CombinedRegistry::all(), vec![TypeId::of::<Injected<A>>()]);
+        assert_eq!(CombinedRegistry::metas().len(), 1);
     }
-}
 
+    // Another component bound to the same type as A, e.g. a fake
+    struct FakeA { }
+    impl Component for FakeA {
+        fn build(_: &ComponentRepository) -> FakeA { FakeA{} }
+        fn meta() -> ComponentMeta<Box<FakeA>> {
+            ComponentMeta {
+                type_id: TypeId::of::<Injected<A>>(),
+                name: "FakeA",
+                build: Box::new(|_| Box::new(FakeA{})),
+                depends_on: vec![ ],
+                requires: vec![ ],
+                scope: ComponentScope::Singleton,
+                profiles: &[],
+                enabled_if: None
+            }
+        }
+    }
+    impl ComponentLifecycle for FakeA {}
+
+    component_registry!(FakeRegistry, [FakeA]);
+    combine_component_registry!(ConflictingRegistry, [DummyRegistry, FakeRegistry]);
+    combine_component_registry!(OverriddenRegistry, [DummyRegistry, DummyRegistry2], overrides = [FakeRegistry]);
+
+    #[test]
+    #[should_panic(expected = "Conflicting registrations: A in DummyRegistry and FakeA in FakeRegistry")]
+    fn combine_registries_conflict() {
+        ConflictingRegistry::all();
+    }
+
+    #[test]
+    fn combine_registries_validate() {
+        assert_eq!(CombinedRegistry::validate(), Ok(()));
+        assert_eq!(OverriddenRegistry::validate(), Ok(()));
+        assert_eq!(
+            ConflictingRegistry::validate(),
+            Err(RegistryConflict {
+                component: "FakeA",
+                registry: "FakeRegistry",
+                other_component: "A",
+                other_registry: "DummyRegistry"
+            })
+        );
+    }
+
+    #[test]
+    fn combine_registries_overrides() {
+        assert_eq!(OverriddenRegistry::all(), vec![TypeId::of::<Injected<A>>()]);
+        assert_eq!(OverriddenRegistry::get(TypeId::of::<Injected<A>>()).unwrap().name, "FakeA");
+    }
+
+    // Counts how many times its meta is built
+    static C_METAS: std::sync::atomic::AtomicUsize = std::sync::atomic::AtomicUsize::new(0);
+    struct C { }
+    impl Component for C {
+        fn build(_: &ComponentRepository) -> C { C{} }
+        fn meta() -> ComponentMeta<Box<C>> {
+            C_METAS.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            ComponentMeta {
+                type_id: TypeId::of::<Injected<C>>(),
+                name: "C",
+                build: Box::new(|_| Box::new(C{})),
+                depends_on: vec![ ],
+                requires: vec![ ],
+                scope: ComponentScope::Singleton,
+                profiles: &[],
+                enabled_if: None
+            }
+        }
+    }
+    impl ComponentLifecycle for C {}
+
+    component_registry!(TableRegistry, [A, C]);
+
+    #[test]
+    fn component_registry_table_is_built_once() {
+        for _ in 0..3 {
+            assert_eq!(TableRegistry::get(TypeId::of::<Injected<C>>()).unwrap().name, "C");
+            assert!(TableRegistry::get(TypeId::of::<i32>()).is_none());
+            assert_eq!(
+                TableRegistry::all(),
+                vec![TypeId::of::<Injected<A>>(), TypeId::of::<Injected<C>>()]
+            );
+            assert_eq!(Registry::of::<TableRegistry>().len(), 2);
+        }
+        assert_eq!(C_METAS.load(std::sync::atomic::Ordering::SeqCst), 1);
+
+        // Lookups hand out the meta in the table rather than a copy
+        let c = TypeId::of::<Injected<C>>();
+        assert!(std::ptr::eq(TableRegistry::get(c).unwrap(), TableRegistry::get(c).unwrap()));
+        assert!(std::ptr::eq(TableRegistry::metas()[1], TableRegistry::get(c).unwrap()));
+    }
+
+    struct B { }
+    impl Component for B {
+        fn build(_: &ComponentRepository) -> B { B{} }
+        fn meta() -> ComponentMeta<Box<B>> {
+            ComponentMeta {
+                type_id: TypeId::of::<Injected<B>>(),
+                name: "B",
+                build: Box::new(|_| Box::new(B{})),
+                depends_on: vec![ TypeId::of::<Injected<A>>() ],
+                requires: vec![ (TypeId::of::<Injected<A>>(), "A") ],
+                scope: ComponentScope::Singleton,
+                profiles: &[],
+                enabled_if: None
+            }
+        }
+    }
+    impl ComponentLifecycle for B {}
+
+    #[test]
+    fn registry_builder() {
+        let registry = RegistryBuilder::new()
+            .register::<A>()
+            .register_if::<B>(false)
+            .build();
+        assert_eq!(registry.all(), vec![TypeId::of::<Injected<A>>()]);
+        assert!(registry.get(TypeId::of::<Injected<B>>()).is_none());
+
+        let registry = RegistryBuilder::new()
+            .register::<B>()
+            .merge(RegistryBuilder::new().registry::<CombinedRegistry>())
+            .build();
+        assert_eq!(
+            registry.all(),
+            vec![TypeId::of::<Injected<B>>(), TypeId::of::<Injected<A>>()]
+        );
+        assert_eq!(
+            registry.get(TypeId::of::<Injected<B>>()).unwrap().depends_on,
+            vec![TypeId::of::<Injected<A>>()]
+        );
+
+        // Profiles selected by either builder are selected
+        let registry = RegistryBuilder::new()
+            .profiles(&["dev"])
+            .merge(RegistryBuilder::new().profiles(&["test", "dev"]))
+            .build();
+        assert_eq!(registry.profiles(), &["dev".to_string(), "test".to_string()]);
+    }
+
+    #[test]
+    fn registry_order_is_kept_while_it_can_be() {
+        let a = TypeId::of::<Injected<A>>();
+        let b = TypeId::of::<Injected<B>>();
+
+        let mut registry = Registry::default();
+        registry.extend_order(Some(vec![a]));
+        registry.insert(A::meta().into());
+        registry.insert(B::meta().into());
+        assert_eq!(registry.order(), Some(&[a, b][..]));
+
+        // B depends on A, which would start after it
+        let mut registry = Registry::default();
+        registry.extend_order(Some(vec![b]));
+        registry.insert(B::meta().into());
+        registry.insert(A::meta().into());
+        assert_eq!(registry.order(), None);
+    }
+}
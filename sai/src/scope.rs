@@ -0,0 +1,263 @@
+use std::any::TypeId;
+use std::collections::{HashMap, HashSet};
+use std::time::{Duration, Instant};
+use super::{Component, ComponentMeta, ComponentRepository, ComponentScope, Injected, Registry, StopReport};
+use super::dependency::downcast_component;
+use super::executor::block_on;
+use super::lazy::LazyContext;
+use super::handle::SystemHandle;
+use super::system::{stop_component, StartedComponent};
+use std::sync::Arc;
+
+/// A Scope is a short-lived child container of a started [System](struct.System.html),
+/// e.g. for the duration of a web request.
+///
+/// Components in the scoped registry of the system are built and started on demand, at most
+/// once per scope unless they are transient. They can depend on each other and on any singleton of the parent system.
+/// When the scope ends, the scoped components are stopped and dropped in the reverse order of
+/// startup.
+///
+/// ```
+/// use sai::{Component, Injected, Registry, System, component_registry};
+///
+/// #[derive(Component)]
+/// struct Db {}
+///
+/// #[derive(Component)]
+/// struct RequestContext {
+///     #[injected]
+///     db: Injected<Db>
+/// }
+///
+/// component_registry!(RootRegistry, [Db]);
+///
+/// #[tokio::main]
+/// async fn main() {
+///     let mut system: System<RootRegistry> = System::new()
+///         .with_scoped_registry(Registry::builder().register::<RequestContext>().build());
+///     system.start().await;
+///
+///     // For each request
+///     let mut scope = system.scope();
+///     let context = scope.get::<RequestContext>().await.unwrap();
+///     // ... handle the request
+///     drop(context);
+///     scope.end().await.assert_no_leaks();
+///
+///     system.stop().await;
+/// }
+/// ```
+///
+/// A scoped component that is still referenced when the scope ends is waited for until the
+/// stop timeout of the system, then stopped via `ComponentLifecycle::stop_shared` and reported,
+/// like `System::stop` does.
+///
+/// A Scope that is dropped without calling `end` stops its components as well, by blocking the
+/// current thread like `DropPolicy::Stop` does. Prefer `end` where a scope can be awaited.
+///
+/// A Scope doesn't borrow the system, so it can be moved into a spawned task.
+pub struct Scope {
+    /*
+     * Singletons of the parent system, including lazy ones that are started on demand
+     */
+    lazy: Arc<LazyContext>,
+    handle: SystemHandle,
+    registry: Arc<Registry>,
+
+    /*
+     * Scoped components built in this scope, except transient ones
+     */
+    components: HashMap<TypeId, Injected<dyn Component>>,
+
+    /*
+     * Every instance built in this scope in the order of startup, including transient
+     * components of the parent system
+     */
+    started: Vec<StartedComponent>,
+
+    /*
+     * The stop timeout of the system, see `System::with_stop_timeout`
+     */
+    stop_timeout: Duration
+}
+
+/*
+ * A component to build, after the dependencies that are planned for it
+ */
+struct Step {
+    type_id: TypeId,
+    planned: Vec<TypeId>
+}
+
+impl Scope {
+
+    pub(crate) fn new(lazy: Arc<LazyContext>, handle: SystemHandle, registry: Arc<Registry>, stop_timeout: Duration) -> Self {
+        Scope {
+            lazy,
+            handle,
+            registry,
+            components: HashMap::new(),
+            started: Vec::new(),
+            stop_timeout
+        }
+    }
+
+    /// Get a component from this scope, building and starting it (and its scoped dependencies)
+    /// if needed.
+    ///
+    /// Components that are not in the scoped registry are looked up in the parent system, and
+    /// started if they are lazy. Transient components are built anew for every dependent and
+    /// every call, and belong to the scope. `None` is returned if the component can be found in
+    /// neither.
+    pub async fn get<C: Component + Sync + 'static>(&mut self) -> Option<Injected<C>> {
+        let type_id = TypeId::of::<Injected<C>>();
+        let component = self.get_by_typeid(type_id).await?;
+        downcast_component(&component)
+    }
+
+    async fn get_by_typeid(&mut self, type_id: TypeId) -> Option<Injected<dyn Component>> {
+        if let Some(component) = self.components.get(&type_id) {
+            return Some(component.clone());
+        }
+        if self.meta(type_id).is_none() {
+            if !self.lazy.registry().contains(type_id) {
+                return None;
+            }
+            return Some(self.lazy.get(type_id).await);
+        }
+
+        let mut plan = Vec::new();
+        self.plan(type_id, &mut plan, &mut HashSet::new());
+        let newly_started = self.started.len();
+
+        let registry = self.registry.clone();
+        let lazy = self.lazy.clone();
+
+        /* Instances built for the steps whose dependent hasn't been built yet */
+        let mut built: Vec<Injected<dyn Component>> = Vec::new();
+        for step in plan {
+            let meta = registry
+                .get(step.type_id)
+                .or_else(|| lazy.registry().get(step.type_id))
+                .unwrap();
+
+            let mut dependencies: Vec<(TypeId, Injected<dyn Component>)> = step.planned
+                .iter()
+                .cloned()
+                .zip(built.split_off(built.len() - step.planned.len()))
+                .collect();
+            for dep in meta.depends_on.iter() {
+                if step.planned.contains(dep) {
+                    continue;
+                }
+                let component = match self.components.get(dep) {
+                    Some(component) => component.clone(),
+                    // A singleton of the parent system
+                    None => lazy.get(*dep).await
+                };
+                dependencies.push((*dep, component));
+            }
+
+            // The repository isn't Send, so it mustn't be held across an await
+            let mut component = {
+                let mut repository = ComponentRepository::new();
+                repository.insert_with_typeid(TypeId::of::<LazyContext>(), lazy.clone());
+                repository.insert_with_typeid(TypeId::of::<SystemHandle>(), self.handle.clone());
+                for (dep, component) in dependencies {
+                    repository.insert_with_typeid(dep, component);
+                }
+                (meta.build)(&repository)
+            };
+            component.start().await;
+
+            let component = Injected::from(component);
+            self.started.push(StartedComponent {
+                type_id: step.type_id,
+                name: meta.name,
+                component: component.clone()
+            });
+            if meta.scope != ComponentScope::Transient {
+                self.components.insert(step.type_id, component.clone());
+            }
+            built.push(component);
+        }
+
+        for started in self.started[newly_started..].iter() {
+            started.component.post_start().await;
+        }
+
+        built.pop()
+    }
+
+    /// Components that need to be built for `type_id`, dependencies first. Like
+    /// `System::start`, a transient component appears once per dependent.
+    /// False if `type_id` doesn't have to be built.
+    fn plan(&self, type_id: TypeId, plan: &mut Vec<Step>, visiting: &mut HashSet<TypeId>) -> bool {
+        let meta = match self.meta(type_id) {
+            Some(meta) => meta,
+            None => return false
+        };
+        let transient = meta.scope == ComponentScope::Transient;
+        if !transient && (self.components.contains_key(&type_id) || plan.iter().any(|s| s.type_id == type_id)) {
+            return false;
+        }
+        if !visiting.insert(type_id) {
+            panic!("Unable to handle circular dependency in the scope");
+        }
+
+        let planned = meta.depends_on
+            .iter()
+            .cloned()
+            .filter(|dep| self.plan(*dep, plan, visiting))
+            .collect();
+
+        visiting.remove(&type_id);
+        plan.push(Step {
+            type_id,
+            planned
+        });
+        true
+    }
+
+    /// Components that are built by the scope: scoped ones, and transient ones of the parent
+    fn meta(&self, type_id: TypeId) -> Option<&ComponentMeta<Box<dyn Component>>> {
+        self.registry.get(type_id).or_else(|| {
+            self.lazy
+                .registry()
+                .get(type_id)
+                .filter(|meta| meta.scope == ComponentScope::Transient)
+        })
+    }
+
+    /// Stop and **drop** all components created in this scope in the reverse order of startup.
+    ///
+    /// Components obtained from this scope should be dropped before calling this, see
+    /// `StopReport::shared_components` for the ones that weren't.
+    pub async fn end(mut self) -> StopReport {
+        self.stop().await
+    }
+
+    async fn stop(&mut self) -> StopReport {
+        self.components = HashMap::new();
+
+        for started in self.started.iter().rev() {
+            started.component.pre_stop().await;
+        }
+
+        let deadline = Instant::now() + self.stop_timeout;
+        let mut report = StopReport::default();
+        while let Some(started) = self.started.pop() {
+            stop_component(started, deadline, &mut report).await;
+        }
+        report
+    }
+}
+
+impl Drop for Scope {
+    fn drop(&mut self) {
+        if self.started.is_empty() || std::thread::panicking() {
+            return;
+        }
+        block_on(self.stop());
+    }
+}
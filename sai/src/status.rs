@@ -0,0 +1,131 @@
+use std::any::TypeId;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+use super::Registry;
+
+/// Where a component is in its lifecycle, see `System::status`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ComponentState {
+    NotBuilt,
+    Starting,
+    Started,
+    Stopping,
+    Stopped,
+    /// Building or starting the component panicked, e.g. a provider returned an error
+    Failed
+}
+
+/// A snapshot of a component of a System, see `System::status`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ComponentStatus {
+    pub name: &'static str,
+    pub state: ComponentState,
+    /// How long building and starting the component took, once it has started
+    pub start_duration: Option<Duration>,
+    /// The components it depends on
+    pub dependencies: Vec<&'static str>,
+    /// The components that depend on it
+    pub dependents: Vec<&'static str>
+}
+
+/*
+ * The state of each component of a System, shared with its lazy context.
+ * Transient components report the state of their latest instance.
+ */
+#[derive(Default)]
+pub(crate) struct Tracker {
+    states: Mutex<HashMap<TypeId, (ComponentState, Option<Duration>)>>,
+
+    /* Whether the system has started and is not stopping */
+    up: AtomicBool
+}
+
+impl Tracker {
+
+    pub(crate) fn set_up(&self, up: bool) {
+        self.up.store(up, Ordering::SeqCst);
+    }
+
+    pub(crate) fn is_up(&self) -> bool {
+        self.up.load(Ordering::SeqCst)
+    }
+
+    /// Mark a component as starting. It's marked as failed unless the guard is `done`.
+    pub(crate) fn start(&self, type_id: TypeId) -> Starting<'_> {
+        self.set(type_id, ComponentState::Starting);
+        Starting {
+            tracker: self,
+            type_id,
+            since: Instant::now(),
+            done: false
+        }
+    }
+
+    pub(crate) fn set(&self, type_id: TypeId, state: ComponentState) {
+        let mut states = self.states.lock().unwrap();
+        let entry = states.entry(type_id).or_insert((state, None));
+        entry.0 = state;
+    }
+
+    pub(crate) fn get(&self, type_id: TypeId) -> (ComponentState, Option<Duration>) {
+        self.states
+            .lock()
+            .unwrap()
+            .get(&type_id)
+            .cloned()
+            .unwrap_or((ComponentState::NotBuilt, None))
+    }
+}
+
+/// The status of every active component of `registry`, in the order they were registered
+pub(crate) fn status(registry: &Registry, tracker: &Tracker) -> Vec<ComponentStatus> {
+    let all = registry.all();
+    let name = |tid: &TypeId| registry.get(*tid).map(|meta| meta.name);
+
+    all.iter()
+        .map(|tid| {
+            let meta = registry.get(*tid).unwrap();
+            let (state, start_duration) = tracker.get(*tid);
+            ComponentStatus {
+                name: meta.name,
+                state,
+                start_duration,
+                dependencies: meta.depends_on.iter().filter_map(name).collect(),
+                dependents: all
+                    .iter()
+                    .filter(|other| registry.get(**other).unwrap().depends_on.contains(tid))
+                    .filter_map(name)
+                    .collect()
+            }
+        })
+        .collect()
+}
+
+pub(crate) struct Starting<'a> {
+    tracker: &'a Tracker,
+    type_id: TypeId,
+    since: Instant,
+    done: bool
+}
+
+impl Starting<'_> {
+    pub(crate) fn done(mut self) {
+        self.done = true;
+        let duration = self.since.elapsed();
+        self.tracker.states
+            .lock()
+            .unwrap()
+            .insert(self.type_id, (ComponentState::Started, Some(duration)));
+    }
+}
+
+impl Drop for Starting<'_> {
+    fn drop(&mut self) {
+        // Unwinding from a panic while building or starting the component
+        if !self.done {
+            self.tracker.set(self.type_id, ComponentState::Failed);
+        }
+    }
+}
@@ -1,12 +1,185 @@
-use std::any::TypeId;
+use std::any::{Any, TypeId};
 use std::collections::HashSet;
-use super::{Component, ComponentRepository, Injected, ComponentRegistry};
+use std::fmt;
+use std::marker::PhantomData;
+use std::sync::{Arc, Mutex, Weak};
+use std::time::{Duration, Instant};
+use super::{Component, ComponentMeta, ComponentRepository, ComponentScope, DisabledComponent, Injected, ComponentRegistry, Registrable, Registry, RegistryBuilder, Scope, ValidationError};
+use super::provider::{Provided, ProvidedValue};
+use super::dependency::downcast_component;
+use super::executor::{block_on, sleep};
+use super::lazy::LazyContext;
+use super::status::{self, ComponentState, ComponentStatus, Tracker};
+use super::handle::{Request, Requests, SystemHandle};
+use super::registry::Config;
 
 enum SystemState {
     Stopped,
     Started
 }
 
+/// The reason why `System::get` can't return a component
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GetError {
+    /// The system has not been started yet, or has been stopped
+    NotStarted,
+    /// The component is not started by the system, e.g. it's not in the registry,
+    /// not reachable from the entrypoint, or a transient component
+    NotFound(String)
+}
+
+impl fmt::Display for GetError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GetError::NotStarted => write!(f, "The system is not started"),
+            GetError::NotFound(name) => write!(f, "{} is not started by the system", name)
+        }
+    }
+}
+
+impl std::error::Error for GetError {}
+
+/// The reason why `System::restart` can't restart a component
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RestartError {
+    /// The system is not started, or it stopped before handling the request
+    NotStarted,
+    /// The component is not in the system, or it's not started
+    NotFound(String),
+    /// Only singletons started by `System::start` can be restarted, i.e. neither transient nor
+    /// lazy components, nor components that a lazy component depends on
+    Unsupported(&'static str),
+    /// Nothing handled the request in time, see `SystemHandle::restart`
+    Timeout
+}
+
+impl fmt::Display for RestartError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RestartError::NotStarted => write!(f, "The system is not started"),
+            RestartError::NotFound(name) => write!(f, "{} is not started by the system", name),
+            RestartError::Unsupported(name) => write!(f, "{} can't be restarted", name),
+            RestartError::Timeout => write!(f, "The restart request was not handled in time")
+        }
+    }
+}
+
+impl std::error::Error for RestartError {}
+
+const STOP_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// How long `System::stop` waits for outside references to a component to drop by default
+pub const DEFAULT_STOP_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// What a System does when it's dropped while it's started, see `System::with_drop_policy`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DropPolicy {
+    /// Leave the components as they are, and print a warning to stderr with `eprintln!`.
+    /// This is the default.
+    #[default]
+    Warn,
+    /// Stop the system, blocking the current thread until it's stopped
+    Stop,
+    /// Panic, e.g. to catch a missing `stop` in tests
+    Panic
+}
+
+/// The outcome of `System::stop`
+///
+/// It keeps track of every stopped component, so it can also tell whether any of them
+/// outlives the system:
+/// ```ignore
+/// system.stop().await.assert_no_leaks();
+/// ```
+#[derive(Debug, Default)]
+pub struct StopReport {
+    shared: Vec<SharedComponent>,
+    stopped: Vec<Stopped>
+}
+
+/*
+ * A stopped component, and the value it provides if it's a provider or an instance
+ */
+#[derive(Debug)]
+struct Stopped {
+    name: &'static str,
+    component: Weak<dyn Component>,
+    value: Option<ProvidedValue>
+}
+
+impl Stopped {
+    fn references(&self) -> usize {
+        let component = self.component.strong_count();
+        let value = self.value.as_ref().map(|value| value.references(component > 0)).unwrap_or(0);
+        component + value
+    }
+}
+
+/// A component that was still referenced outside of the system when it was stopped.
+///
+/// It was stopped via `ComponentLifecycle::stop_shared` and is only dropped once the last
+/// reference goes away.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SharedComponent {
+    pub name: &'static str,
+    /// Live references to the component other than the system's own one. For a provider or
+    /// an instance, these are references to its value.
+    pub references: usize
+}
+
+impl StopReport {
+    /// Components that couldn't be stopped exclusively, in the order they were stopped
+    pub fn shared_components(&self) -> &[SharedComponent] {
+        &self.shared
+    }
+
+    /// Whether every component was exclusively owned by the system when it was stopped
+    pub fn is_clean(&self) -> bool {
+        self.shared.is_empty()
+    }
+
+    /// Stopped components that are still alive at the time of calling, i.e. referenced by an
+    /// `Injected` that outlives the system. For a provider or an instance, references to its
+    /// value count as well.
+    pub fn leaks(&self) -> Vec<Leak> {
+        self.stopped
+            .iter()
+            .filter_map(|stopped| match stopped.references() {
+                0 => None,
+                references => Some(Leak { name: stopped.name, references })
+            })
+            .collect()
+    }
+
+    /// Panic if any stopped component is still alive, see `StopReport::leaks`
+    pub fn assert_no_leaks(&self) {
+        let leaks = self.leaks();
+        if !leaks.is_empty() {
+            let details: Vec<String> = leaks
+                .iter()
+                .map(|l| format!("{} ({} references)", l.name, l.references))
+                .collect();
+            panic!("Components outlived the system: {}", details.join(", "));
+        }
+    }
+}
+
+/// A stopped component that is still referenced, see `StopReport::leaks`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Leak {
+    pub name: &'static str,
+    pub references: usize
+}
+
+/*
+ * A started instance and its name for diagnostics
+ */
+pub(crate) struct StartedComponent {
+    pub(crate) type_id: TypeId,
+    pub(crate) name: &'static str,
+    pub(crate) component: Injected<dyn Component>
+}
+
 /// **A system is a collection of components** + the ability to control the lifecycle
 /// of components in a way meeting the dependency requirement of components, e.g. start/stop them.
 ///
@@ -38,24 +211,105 @@ enum SystemState {
 ///     Ok(())
 /// }
 /// ```
-pub struct System<T> where T: ComponentRegistry {
+///
+/// A System can also run a [Registry](struct.Registry.html) that is built at runtime,
+/// see `System::from_registry`.
+pub struct System<T = Registry> {
 
     /// If this is set, then the system will only start
     /// components that can be reached by this entrypoint.
     pub entrypoint: Option<TypeId>,
 
     /*
-     * Just a dummy object to store the type
-     * Any better way?
+     * Meta information of all components in the system,
+     * collected once when the system is created.
      */
-     __dummy: T,
+    registry: Arc<Registry>,
+
+    /*
+     * Components that are created per scope, see `System::scope`
+     */
+    scoped_registry: Arc<Registry>,
 
     /*
      Initiated components
      */
     component_repository: ComponentRepository,
 
-    state: SystemState
+    /*
+     * Every started instance in the order of startup,
+     * including each instance of transient components
+     */
+    started: Arc<Mutex<Vec<StartedComponent>>>,
+
+    /*
+     * Starts lazy components while the system is running
+     */
+    lazy: Option<Arc<LazyContext>>,
+
+    /*
+     * The state of each component, see `System::status`
+     */
+    tracker: Arc<Tracker>,
+
+    /*
+     * Made through a SystemHandle, see `System::handle_requests`
+     */
+    requests: Arc<Requests>,
+
+    /*
+     * See `System::with_stop_timeout`
+     */
+    stop_timeout: Duration,
+
+    /*
+     * See `System::with_drop_policy`
+     */
+    drop_policy: DropPolicy,
+
+    state: SystemState,
+
+    /*
+     * Just a marker to remember where the registry comes from
+     */
+    __registry: PhantomData<T>
+}
+
+/// Stop a component once nothing outside of the system references it, or the deadline has passed
+pub(crate) async fn stop_component(started: StartedComponent, deadline: Instant, report: &mut StopReport) {
+    let StartedComponent { name, mut component, .. } = started;
+
+    // Dependents of a provider or an instance hold its value rather than the component
+    let value = component.provided_value();
+    let references = |component: &Injected<dyn Component>| {
+        let value = value.as_ref().map(|value| value.references(true)).unwrap_or(0);
+        Arc::strong_count(component) - 1 + value
+    };
+
+    while references(&component) > 0 && Instant::now() < deadline {
+        sleep(STOP_POLL_INTERVAL).await;
+    }
+
+    let references = references(&component);
+    match component.get_mut() {
+        Some(owned_component) if references == 0 => owned_component.stop().await,
+        _ => {
+            report.shared.push(SharedComponent {
+                name,
+                references
+            });
+            component.stop_shared().await;
+        }
+    }
+
+    report.stopped.push(Stopped {
+        name,
+        component: Arc::downgrade(&component),
+        value
+    });
+
+    // Force rust to drop memory
+    drop(component);
 }
 
 impl<T> System<T> where T: ComponentRegistry {
@@ -71,12 +325,7 @@ impl<T> System<T> where T: ComponentRegistry {
     /// ```
     ///
     pub fn new() -> Self {
-        return System {
-            entrypoint: None,
-            __dummy: T::new(),
-            component_repository: ComponentRepository::new(),
-            state: SystemState::Stopped
-        }
+        System::with_registry(Registry::of::<T>(), None)
     }
 
     /// Similar to System::new() but allow you to specified an entrypoint for the system.
@@ -84,14 +333,380 @@ impl<T> System<T> where T: ComponentRegistry {
     pub fn with_entrypoint(
         entrypoint: TypeId
     ) -> Self {
+        System::with_registry(Registry::of::<T>(), Some(entrypoint))
+    }
+}
+
+impl System<Registry> {
+
+    /// Create a new system from a [Registry](struct.Registry.html) built at runtime.
+    ///
+    /// __Example__
+    /// ```ignore
+    /// use sai::{Registry, System};
+    ///
+    /// let registry = Registry::builder()
+    ///     .register::<Db>()
+    ///     .register_if::<Cache>(config.cache_enabled)
+    ///     .build();
+    ///
+    /// let system = System::from_registry(registry);
+    /// ```
+    pub fn from_registry(registry: Registry) -> Self {
+        System::with_registry(registry, None)
+    }
+
+    /// Create a [SystemBuilder](struct.SystemBuilder.html), which can also put existing values
+    /// into the system.
+    pub fn builder() -> SystemBuilder {
+        SystemBuilder::new()
+    }
+}
+
+/// A builder for a System.
+///
+/// Besides components, values that already exist before the system is built, e.g. a runtime
+/// handle or parsed command line arguments, can be added as instances. Other components can
+/// depend on them via `#[injected]` like on any other component.
+///
+/// ```
+/// use sai::{Component, Injected, System, component_registry};
+///
+/// struct Args {
+///     port: u16
+/// }
+///
+/// #[derive(Component)]
+/// struct Server {
+///     #[injected]
+///     args: Injected<Args>
+/// }
+///
+/// component_registry!(RootRegistry, [Server]);
+///
+/// let system = System::builder()
+///     .registry::<RootRegistry>()
+///     .instance(Args { port: 8080 })
+///     .build();
+/// ```
+#[derive(Default)]
+pub struct SystemBuilder {
+    registry: RegistryBuilder,
+    scoped_registry: Registry,
+    entrypoint: Option<TypeId>,
+    stop_timeout: Option<Duration>,
+    drop_policy: DropPolicy,
+    profiles: Vec<String>,
+    config: Option<Config>
+}
+
+impl SystemBuilder {
+
+    pub fn new() -> Self {
+        SystemBuilder::default()
+    }
+
+    /// Add all components of a static ComponentRegistry
+    pub fn registry<T: ComponentRegistry>(mut self) -> Self {
+        self.registry = self.registry.registry::<T>();
+        self
+    }
+
+    /// Add a component (or a provider)
+    pub fn register<C: Registrable>(mut self) -> Self {
+        self.registry = self.registry.register::<C>();
+        self
+    }
+
+    /// Add an existing value that other components can depend on as `Injected<T>`.
+    ///
+    /// The value is considered as started already, so the system will neither start nor stop
+    /// it. It's dropped with the System.
+    pub fn instance<T: Send + Sync + 'static>(mut self, value: T) -> Self {
+        // Kept by the registry, so that the system can be started again
+        let instance = Arc::new(Injected::new(value));
+        self.registry = self.registry.register_meta(ComponentMeta {
+            type_id: TypeId::of::<Injected<T>>(),
+            name: std::any::type_name::<T>(),
+            depends_on: vec![],
+            requires: vec![],
+            build: Box::new(move |_| {
+                Box::new(Provided::ready(std::any::type_name::<T>(), &instance))
+            }),
+            scope: ComponentScope::Singleton,
+            profiles: &[],
+            enabled_if: None
+        });
+        self
+    }
+
+    /// Add an existing component whose lifecycle is controlled by the system like any other
+    /// component, i.e. it is started and stopped by the system.
+    ///
+    /// Its dependencies are not injected, since it's built already.
+    /// As the component is dropped when the system stops, the system can only be started once.
+    pub fn managed_instance<C: Component + Sync + 'static>(mut self, component: C) -> Self {
+        let component = Mutex::new(Some(component));
+        self.registry = self.registry.register_meta(ComponentMeta {
+            type_id: TypeId::of::<Injected<C>>(),
+            name: std::any::type_name::<C>(),
+            depends_on: vec![],
+            requires: vec![],
+            build: Box::new(move |_| {
+                let component = component
+                    .lock()
+                    .unwrap()
+                    .take()
+                    .expect("A managed instance can only be started once");
+                Box::new(component)
+            }),
+            scope: ComponentScope::Singleton,
+            profiles: &[],
+            enabled_if: None
+        });
+        self
+    }
+
+    /// See `System::with_scoped_registry`
+    pub fn scoped_registry(mut self, registry: Registry) -> Self {
+        self.scoped_registry = registry;
+        self
+    }
+
+    /// See `System::with_entrypoint`
+    pub fn entrypoint(mut self, entrypoint: TypeId) -> Self {
+        self.entrypoint = Some(entrypoint);
+        self
+    }
+
+    /// See `System::with_stop_timeout`
+    pub fn stop_timeout(mut self, timeout: Duration) -> Self {
+        self.stop_timeout = Some(timeout);
+        self
+    }
+
+    /// See `System::with_drop_policy`
+    pub fn drop_policy(mut self, policy: DropPolicy) -> Self {
+        self.drop_policy = policy;
+        self
+    }
+
+    /// See `System::with_profiles`
+    pub fn profiles(mut self, profiles: &[&str]) -> Self {
+        self.profiles = profiles.iter().map(|p| p.to_string()).collect();
+        self
+    }
+
+    /// See `System::with_config`
+    pub fn config<F: Fn(&str) -> bool + 'static>(mut self, config: F) -> Self {
+        self.config = Some(Box::new(config));
+        self
+    }
+
+    pub fn build(self) -> System<Registry> {
+        let profiles: Vec<&str> = self.profiles.iter().map(String::as_str).collect();
+        let system = System::with_registry(self.registry.build(), self.entrypoint)
+            .with_scoped_registry(self.scoped_registry)
+            .with_stop_timeout(self.stop_timeout.unwrap_or(DEFAULT_STOP_TIMEOUT))
+            .with_drop_policy(self.drop_policy)
+            .with_profiles(&profiles);
+        match self.config {
+            Some(config) => system.with_config(config),
+            None => system
+        }
+    }
+}
+
+impl<T> System<T> {
+
+    fn with_registry(registry: Registry, entrypoint: Option<TypeId>) -> Self {
         return System {
-            entrypoint: Some(entrypoint),
-            __dummy: T::new(),
+            entrypoint,
+            registry: Arc::new(registry),
+            scoped_registry: Arc::new(Registry::default()),
             component_repository: ComponentRepository::new(),
-            state: SystemState::Stopped
+            started: Arc::new(Mutex::new(Vec::new())),
+            lazy: None,
+            tracker: Arc::new(Tracker::default()),
+            requests: Arc::new(Requests::default()),
+            stop_timeout: DEFAULT_STOP_TIMEOUT,
+            drop_policy: DropPolicy::default(),
+            state: SystemState::Stopped,
+            __registry: PhantomData
         }
     }
 
+    /// Set the registry of components that are created per [Scope](struct.Scope.html) rather than
+    /// once for the whole system.
+    ///
+    /// Scoped components can depend on components of this system, but not the other way around.
+    pub fn with_scoped_registry(mut self, registry: Registry) -> Self {
+        self.scoped_registry = Arc::new(registry);
+        self
+    }
+
+    /// Select the active profiles of the system, for both its registry and scoped registry.
+    ///
+    /// Components declared with `#[component(profile = "...")]` are only started when one of
+    /// their profiles is active, see [Registry](struct.Registry.html).
+    /// ```ignore
+    /// let mut system: System<RootRegistry> = System::new().with_profiles(&["prod"]);
+    /// ```
+    pub fn with_profiles(mut self, profiles: &[&str]) -> Self {
+        Arc::get_mut(&mut self.registry)
+            .expect("Profiles can only be selected before the system starts")
+            .set_profiles(profiles);
+        Arc::get_mut(&mut self.scoped_registry)
+            .expect("Profiles can only be selected before the system starts")
+            .set_profiles(profiles);
+        self
+    }
+
+    /// Enable or disable components declared with `#[component(enabled_if = "...")]` by a
+    /// configuration lookup, for both the registry and the scoped registry.
+    ///
+    /// ```ignore
+    /// let mut system: System<RootRegistry> = System::new()
+    ///     .with_config(|key| settings.get_bool(key).unwrap_or(false));
+    ///
+    /// for c in system.disabled_components() {
+    ///     println!("{} is disabled by {}", c.name, c.condition);
+    /// }
+    /// ```
+    ///
+    /// Components with an `enabled_if` key are disabled unless a configuration enables them.
+    pub fn with_config<F: Fn(&str) -> bool>(mut self, config: F) -> Self {
+        Arc::get_mut(&mut self.registry)
+            .expect("The configuration can only be applied before the system starts")
+            .set_config(&config);
+        Arc::get_mut(&mut self.scoped_registry)
+            .expect("The configuration can only be applied before the system starts")
+            .set_config(&config);
+        self
+    }
+
+    /// Components disabled by the configuration, see `System::with_config`
+    pub fn disabled_components(&self) -> Vec<DisabledComponent> {
+        self.registry.disabled()
+    }
+
+    /// Check that the dependencies of every component can be satisfied with the selected
+    /// profiles and configuration. `start` panics with the first error otherwise.
+    pub fn validate(&self) -> Result<(), Vec<ValidationError>> {
+        let mut errors = self.registry.validate().err().unwrap_or_default();
+        if let Err(scoped) = self.scoped_registry.validate_scoped(&self.registry) {
+            errors.extend(scoped);
+        }
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// The state of every component of the system, in the order they were registered.
+    ///
+    /// ```ignore
+    /// for component in system.status() {
+    ///     println!("{}: {:?} in {:?}", component.name, component.state, component.start_duration);
+    /// }
+    /// ```
+    pub fn status(&self) -> Vec<ComponentStatus> {
+        status::status(&self.registry, &self.tracker)
+    }
+
+    /// Set how long `stop` waits for references to a component held outside of the system,
+    /// e.g. by a spawned task, to be dropped. Defaults to 5 seconds.
+    ///
+    /// A component that is still shared after the timeout is stopped via
+    /// `ComponentLifecycle::stop_shared` instead of `stop`, and reported by `stop`.
+    /// The timeout applies to the whole shutdown rather than to each component.
+    pub fn with_stop_timeout(mut self, timeout: Duration) -> Self {
+        self.stop_timeout = timeout;
+        self
+    }
+
+    /// Set what happens when the system is dropped while it's started, e.g. when `main`
+    /// returns early with `?` before reaching `stop`. By default, a warning is printed to
+    /// stderr and the components are left as they are.
+    ///
+    /// `DropPolicy::Stop` stops the system by blocking the current thread instead. Only opt in
+    /// if no component's `stop` needs the current thread to make progress, which rules out
+    /// e.g. a single threaded runtime.
+    ///
+    /// Nothing happens if the system is dropped while the thread is panicking.
+    pub fn with_drop_policy(mut self, policy: DropPolicy) -> Self {
+        self.drop_policy = policy;
+        self
+    }
+
+    /// Create a child [Scope](struct.Scope.html) of a started system, e.g. for a web request.
+    ///
+    /// ```ignore
+    /// let mut scope = system.scope();
+    /// let context = scope.get::<RequestContext>().await.unwrap();
+    /// ...
+    /// scope.end().await;
+    /// ```
+    pub fn scope(&self) -> Scope {
+        match self.state {
+            SystemState::Started => Scope::new(
+                self.lazy.clone().expect("This won't happen"),
+                SystemHandle::new(self.registry.clone(), self.tracker.clone(), self.requests.clone()),
+                self.scoped_registry.clone(),
+                self.stop_timeout
+            ),
+            SystemState::Stopped => panic!("Unable to create a scope from a stopped system")
+        }
+    }
+
+    /// Get a started component, or the value of a provider or an instance.
+    ///
+    /// ```ignore
+    /// system.start().await;
+    /// let controller: Injected<FooController> = system.get::<FooController>().unwrap();
+    /// ```
+    ///
+    /// Use `.ok()` if an `Option` is all you need.
+    pub fn get<C: Any + Send + Sync>(&self) -> Result<Injected<C>, GetError> {
+        let not_found = GetError::NotFound(std::any::type_name::<C>().to_string());
+        let component = self.get_by_typeid(TypeId::of::<Injected<C>>())
+            .map_err(|e| match e {
+                GetError::NotFound(_) => not_found.clone(),
+                e => e
+            })?;
+        downcast_component(&component).ok_or(not_found)
+    }
+
+    /// Get a started component as a trait object it implements.
+    ///
+    /// ```ignore
+    /// let handler: Injected<dyn Handler> = system.get_as::<FooController, dyn Handler>(|c| c)?;
+    /// ```
+    pub fn get_as<C, I>(&self, bind: fn(Arc<C>) -> Arc<I>) -> Result<Injected<I>, GetError>
+        where C: Any + Send + Sync, I: ?Sized
+    {
+        let component = self.get::<C>()?;
+        let arc: Arc<C> = (*component).clone();
+        Ok(Injected::from(bind(arc)))
+    }
+
+    /// Get a started component by the type id of its `Injected<T>`
+    pub fn get_by_typeid(&self, type_id: TypeId) -> Result<Injected<dyn Component>, GetError> {
+        if let SystemState::Stopped = self.state {
+            return Err(GetError::NotStarted);
+        }
+
+        self.component_repository
+            .get_by_typeid::<Injected<dyn Component>>(type_id)
+            .cloned()
+            .or_else(|| self.lazy.as_ref().and_then(|lazy| lazy.find(type_id)))
+            .ok_or_else(|| GetError::NotFound(match self.registry.get(type_id) {
+                Some(meta) => meta.name.to_string(),
+                None => format!("{:?}", type_id)
+            }))
+    }
+
     /// Create & start all components in the registry in a topological order.
     /// The topological order is automatically derived by system from analysing `#[injected]`
     /// macro attributes in component definitons.
@@ -107,30 +722,58 @@ impl<T> System<T> where T: ComponentRegistry {
             SystemState::Started => return,
             _ => {},
         };
+        if let Err(errors) = self.validate() {
+            panic!("Invalid system: {}", errors[0]);
+        }
+
+        let lazy = Arc::new(LazyContext::new(
+            self.registry.clone(),
+            &self.started,
+            self.tracker.clone()
+        ));
+        self.component_repository.insert_with_typeid(TypeId::of::<LazyContext>(), lazy.clone());
+        self.lazy = Some(lazy.clone());
+        self.component_repository.insert_with_typeid(
+            TypeId::of::<SystemHandle>(),
+            SystemHandle::new(self.registry.clone(), self.tracker.clone(), self.requests.clone())
+        );
+
         // 1. topology sort
         let sorted_type_ids = self.topological_sort();
 
         for tid in sorted_type_ids {
-            let meta = T::get(tid);
-            match meta {
-                Some(m) => {
-                    let type_id = m.type_id;
-                    // 2. start the component one by one
-                    let mut component = (m.build)(&self.component_repository);
-                    component.start().await;
-
-                    // 3. Insert started component into repo
-                    let injected_component = Injected::from(component);
-                    // Here we need a concrete type so this won't work
-                    // self.component_repository.insert(injected_component);
-                    // Current solution:
-                    self.component_repository.insert_with_typeid(type_id, injected_component);
+            let meta = self.registry.get(tid).expect("This won't happen");
 
-                },
-                None => panic!("This won't happen")
+            // Transient components are only created for their dependents.
+            // A lazy component in the list is injected directly into another component,
+            // so it's started now like a singleton.
+            if meta.scope == ComponentScope::Transient {
+                continue;
+            }
+
+            // Already started by a lazy component during startup
+            if let Some(component) = lazy.find(tid) {
+                self.component_repository.insert_with_typeid(tid, component);
+                continue;
             }
+
+            // 2. start the component one by one
+            let injected_component = self.start_component(tid).await;
+
+            // 3. Insert started component into repo
+            // Here we need a concrete type so this won't work
+            // self.component_repository.insert(injected_component);
+            // Current solution:
+            lazy.insert(tid, injected_component.clone());
+            self.component_repository.insert_with_typeid(tid, injected_component);
         }
 
+        // 4. Everything is up
+        for component in self.started_components() {
+            component.post_start().await;
+        }
+        lazy.set_up();
+        self.tracker.set_up(true);
 
         self.state = SystemState::Started
     }
@@ -146,37 +789,292 @@ impl<T> System<T> where T: ComponentRegistry {
     /// signal::ctrl_c().await?;
 
     /// println!("System shutting down...");
-    /// system.stop().await;
+    /// let report = system.stop().await;
     /// println!("System shutted down.");
     ///
+    /// for c in report.shared_components() {
+    ///     println!("{} was still in use", c.name);
+    /// }
     /// ```
-    pub async fn stop(&mut self) {
+    ///
+    /// A component still referenced outside of the system (e.g. an `Injected` moved into a
+    /// spawned task) is waited for until the stop timeout, see `System::with_stop_timeout`.
+    pub async fn stop(&mut self) -> StopReport {
+        let mut report = StopReport::default();
         match self.state {
-            SystemState::Stopped => return,
+            SystemState::Stopped => return report,
             _ => {},
         };
-        // 1. topology sort
-        let sorted_type_ids = self.topological_sort();
+        self.tracker.set_up(false);
+        self.requests.clear();
 
-        // In the reversed order of the start
-        for tid in sorted_type_ids.into_iter().rev() {
-            let component: &mut Injected<dyn Component> = self.component_repository.get_by_typeid_mut(tid).unwrap();
-            let owned_component = component.get_mut().unwrap();
-            // This is a bit dangerous
-            // TODO more documentation
-            owned_component.stop().await;
+        // Drop the references held by the repository so that each component is
+        // exclusively owned by `started` once its dependents are gone.
+        self.component_repository = ComponentRepository::new();
+        if let Some(lazy) = self.lazy.take() {
+            lazy.close();
+        }
+
+        for component in self.started_components().into_iter().rev() {
+            component.pre_stop().await;
+        }
 
-            // Force rust to drop memory
-            self.component_repository.remove_by_typeid(tid);
+        let deadline = Instant::now() + self.stop_timeout;
+
+        // In the reversed order of the start
+        loop {
+            let next = self.started.lock().unwrap().pop();
+            match next {
+                Some(started) => {
+                    let type_id = started.type_id;
+                    self.tracker.set(type_id, ComponentState::Stopping);
+                    stop_component(started, deadline, &mut report).await;
+                    self.tracker.set(type_id, ComponentState::Stopped);
+                },
+                None => break
+            };
         }
 
 
         self.component_repository = ComponentRepository::new();
-        self.state = SystemState::Stopped
+        self.state = SystemState::Stopped;
+
+        report
+    }
+
+    /// Stop a started component and the components that depend on it, directly or not, then
+    /// start them again in the same order, with fresh instances. Returns the names of the
+    /// restarted components, starting with the given one.
+    ///
+    /// ```ignore
+    /// let restarted = system.restart::<DbPool>().await?;
+    /// ```
+    ///
+    /// The system isn't up while the components restart, see `SystemHandle::is_up`, and
+    /// `Lazy::get` waits for the restart to finish, so restarted components must not call it
+    /// from `start`.
+    /// Components that hold on to an instance elsewhere, e.g. in a spawned task, keep using the
+    /// stopped instance.
+    pub async fn restart<C: Any>(&mut self) -> Result<Vec<&'static str>, RestartError> {
+        self.restart_by_typeid(TypeId::of::<Injected<C>>())
+            .await
+            .map_err(|e| match e {
+                RestartError::NotFound(_) => RestartError::NotFound(std::any::type_name::<C>().to_string()),
+                e => e
+            })
+    }
+
+    /// Restart a component by the type id of its `Injected<T>`, see `System::restart`
+    pub async fn restart_by_typeid(&mut self, type_id: TypeId) -> Result<Vec<&'static str>, RestartError> {
+        if let SystemState::Stopped = self.state {
+            return Err(RestartError::NotStarted);
+        }
+        let lazy = self.lazy.clone().expect("A started system has a lazy context");
+
+        // Started singletons only, in the order of startup
+        let started: Vec<(TypeId, &'static str, ComponentScope)> = self.started
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|c| (c.type_id, c.name, self.registry.get(c.type_id).unwrap().scope))
+            .collect();
+        let singletons: Vec<TypeId> = started
+            .iter()
+            .filter(|(_, _, scope)| *scope != ComponentScope::Transient)
+            .map(|(tid, _, _)| *tid)
+            .collect();
+
+        let meta = self.registry.get(type_id).ok_or_else(|| RestartError::NotFound(format!("{:?}", type_id)))?;
+        if meta.scope == ComponentScope::Transient {
+            return Err(RestartError::Unsupported(meta.name));
+        }
+        if !singletons.contains(&type_id) {
+            return Err(RestartError::NotFound(meta.name.to_string()));
+        }
+
+        // The component and everything that depends on it, including through transient
+        // components, which are not in `singletons`
+        let mut dependents: HashSet<TypeId> = HashSet::new();
+        dependents.insert(type_id);
+        let all = self.registry.all();
+        loop {
+            let found: Vec<TypeId> = all
+                .iter()
+                .filter(|tid| !dependents.contains(*tid))
+                .filter(|tid| self.registry.get(**tid).unwrap().depends_on.iter().any(|dep| dependents.contains(dep)))
+                .cloned()
+                .collect();
+            if found.is_empty() {
+                break;
+            }
+            dependents.extend(found);
+        }
+        let affected: HashSet<TypeId> = singletons.iter().filter(|tid| dependents.contains(*tid)).cloned().collect();
+        for tid in affected.iter() {
+            let meta = self.registry.get(*tid).unwrap();
+            let started_lazily = self.component_repository
+                .get_by_typeid::<Injected<dyn Component>>(*tid)
+                .is_none();
+            if meta.scope != ComponentScope::Singleton || started_lazily {
+                return Err(RestartError::Unsupported(meta.name));
+            }
+        }
+
+        // Lazy components that are started meanwhile would build their own instances of the
+        // affected components, so they wait until the restart is done
+        let _paused = lazy.pause().await;
+        self.tracker.set_up(false);
+
+        // Take the affected instances out of the system, together with the transient instances
+        // that were created for them right before
+        let stopping: Vec<StartedComponent> = {
+            let mut all = self.started.lock().unwrap();
+            let mut kept = Vec::new();
+            let mut stopping = Vec::new();
+            let mut transients = Vec::new();
+            for component in all.drain(..) {
+                match self.registry.get(component.type_id).unwrap().scope {
+                    ComponentScope::Transient => transients.push(component),
+                    _ if affected.contains(&component.type_id) => {
+                        stopping.append(&mut transients);
+                        stopping.push(component);
+                    },
+                    _ => {
+                        kept.append(&mut transients);
+                        kept.push(component);
+                    }
+                }
+            }
+            kept.append(&mut transients);
+            *all = kept;
+            stopping
+        };
+        for tid in affected.iter() {
+            self.component_repository.remove_by_typeid(*tid);
+            lazy.remove(*tid);
+        }
+
+        for started in stopping.iter().rev() {
+            started.component.pre_stop().await;
+        }
+        let deadline = Instant::now() + self.stop_timeout;
+        let mut report = StopReport::default();
+        for started in stopping.into_iter().rev() {
+            let type_id = started.type_id;
+            self.tracker.set(type_id, ComponentState::Stopping);
+            stop_component(started, deadline, &mut report).await;
+            self.tracker.set(type_id, ComponentState::Stopped);
+        }
+
+        let restarted: Vec<TypeId> = singletons.into_iter().filter(|tid| affected.contains(tid)).collect();
+        let mut components = Vec::new();
+        for tid in restarted.iter() {
+            let component = self.start_component(*tid).await;
+            lazy.insert(*tid, component.clone());
+            self.component_repository.insert_with_typeid(*tid, component.clone());
+            components.push(component);
+        }
+        for component in components {
+            component.post_start().await;
+        }
 
+        self.tracker.set_up(true);
+
+        Ok(restarted.iter().map(|tid| self.registry.get(*tid).unwrap().name).collect())
+    }
+
+    /// Handle the requests made through a [SystemHandle](struct.SystemHandle.html), e.g. by an
+    /// admin component, until one of them asks the system to stop.
+    ///
+    /// ```ignore
+    /// system.start().await;
+    /// system.handle_requests().await;
+    /// system.stop().await;
+    /// ```
+    ///
+    /// Restart requests are handled one at a time, see `System::restart`. Requests made while
+    /// nothing handles them are queued, until `SystemHandle::restart` gives up on them.
+    pub async fn handle_requests(&mut self) {
+        loop {
+            match self.requests.next().await {
+                Request::Stop => return,
+                Request::Restart(_, type_id, reply) => {
+                    let result = self.restart_by_typeid(type_id).await;
+                    let _ = reply.send(result);
+                }
+            }
+        }
+    }
+
+    /// Build and start a component together with fresh instances of its transient dependencies
+    async fn start_component(&mut self, type_id: TypeId) -> Injected<dyn Component> {
+        let mut plan = Vec::new();
+        self.transient_plan(type_id, &mut plan);
+        plan.push(type_id);
+
+        let mut component = None;
+        for tid in plan.iter() {
+            let meta = self.registry.get(*tid).expect("This won't happen");
+            let starting = self.tracker.start(*tid);
+            let mut instance = (meta.build)(&self.component_repository);
+            instance.start().await;
+            starting.done();
+
+            let instance = Injected::from(instance);
+            self.started.lock().unwrap().push(StartedComponent {
+                type_id: *tid,
+                name: meta.name,
+                component: instance.clone()
+            });
+
+            if meta.scope == ComponentScope::Transient {
+                // Visible only to the next component being built that depends on it
+                self.component_repository.insert_with_typeid(*tid, instance.clone());
+            }
+            component = Some(instance);
+        }
+
+        // Transient instances must not be shared with other dependents
+        for tid in plan.iter() {
+            if self.registry.get(*tid).map(|m| m.scope) == Some(ComponentScope::Transient) {
+                self.component_repository.remove_by_typeid(*tid);
+            }
+        }
+
+        component.unwrap()
+    }
+
+    /// Every started instance in the order of startup
+    fn started_components(&self) -> Vec<Injected<dyn Component>> {
+        self.started
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|started| started.component.clone())
+            .collect()
+    }
+
+    /// Collect the transient components that need to be created (in order) before `type_id`
+    /// can be built. A transient component appears once per dependent.
+    fn transient_plan(&self, type_id: TypeId, plan: &mut Vec<TypeId>) {
+        let meta = self.registry.get(type_id).expect("This won't happen");
+        for dep in meta.depends_on.iter() {
+            let is_transient = self.registry
+                .get(*dep)
+                .map(|m| m.scope == ComponentScope::Transient)
+                .unwrap_or(false);
+            if is_transient {
+                self.transient_plan(*dep, plan);
+                plan.push(*dep);
+            }
+        }
     }
 
     fn topological_sort(&self) -> Vec<TypeId> {
+        if let Some(order) = self.registry.order() {
+            return self.reachable(order);
+        }
+
         // cycle detection
         let mut in_results: HashSet<TypeId> = HashSet::new();
         let mut result: Vec<TypeId> = Vec::new();
@@ -187,7 +1085,7 @@ impl<T> System<T> where T: ComponentRegistry {
             if self.entrypoint.is_some() {
                 vec![self.entrypoint.unwrap()]
             } else {
-                Self::detect_entrypoints()
+                self.detect_entrypoints()
             }
         };
 
@@ -201,13 +1099,16 @@ impl<T> System<T> where T: ComponentRegistry {
 
         while let Some(current_type_id) = stack.last() {
             // TODO: error handling
-            let current_meta = T::get(*current_type_id).unwrap();
+            let current_meta = self.registry.get(*current_type_id).unwrap();
             let depends_on = &current_meta.depends_on;
             let next_target = depends_on
                 .iter()
                 .find(|tid| !in_results.contains(tid));
             match next_target {
                 Some(t) => {
+                    if !self.registry.contains(*t) {
+                        panic!("{} depends on a component that is not in the registry: {:?}", current_meta.name, t);
+                    }
                     if in_stack.contains(t) {
                         panic!("Unable to handle circular dependency in the system");
                     }
@@ -226,31 +1127,80 @@ impl<T> System<T> where T: ComponentRegistry {
         return result;
     }
 
-    fn detect_entrypoints () -> Vec<TypeId> {
+    /// The components of a fixed order that are reachable from the entrypoints
+    fn reachable(&self, order: &[TypeId]) -> Vec<TypeId> {
+        let mut reached: HashSet<TypeId> = HashSet::new();
+        let mut stack = match self.entrypoint {
+            Some(entrypoint) => vec![entrypoint],
+            None => self.detect_entrypoints()
+        };
+
+        while let Some(tid) = stack.pop() {
+            if !reached.insert(tid) {
+                continue;
+            }
+            let meta = self.registry.get(tid).unwrap();
+            if let Some(dep) = meta.depends_on.iter().find(|dep| !self.registry.contains(**dep)) {
+                panic!("{} depends on a component that is not in the registry: {:?}", meta.name, dep);
+            }
+            stack.extend(meta.depends_on.iter());
+        }
+
+        order
+            .iter()
+            .filter(|tid| reached.contains(tid))
+            .cloned()
+            .collect()
+    }
+
+    fn detect_entrypoints (&self) -> Vec<TypeId> {
         // If a tid has dependecy, it will be flagged here
         let mut flagged = std::collections::HashSet::new();
 
-        let all_tids = T::all();
+        let all_tids = self.registry.all();
         for tid in all_tids {
-            let meta = T::get(tid).unwrap();
-            let depends = meta.depends_on;
-            for t in depends {
-                flagged.insert(t);
+            let meta = self.registry.get(tid).unwrap();
+            for t in &meta.depends_on {
+                flagged.insert(*t);
             }
         }
 
-        T::all()
+        // Lazy components are only started on demand
+        self.registry.all()
             .into_iter()
             .filter(|id| !flagged.contains(&id))
+            .filter(|id| self.registry.get(*id).map(|m| m.scope) != Some(ComponentScope::Lazy))
             .collect()
     }
 }
 
+impl<T> Drop for System<T> {
+    fn drop(&mut self) {
+        if let SystemState::Stopped = self.state {
+            return;
+        }
+        if std::thread::panicking() {
+            return;
+        }
+
+        match self.drop_policy {
+            DropPolicy::Stop => {
+                block_on(self.stop());
+            },
+            DropPolicy::Warn => eprintln!(
+                "Warning: a started sai::System was dropped without being stopped, \
+                 its components were not stopped"
+            ),
+            DropPolicy::Panic => panic!("A started System was dropped without being stopped")
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use async_trait::async_trait;
-    use super::super::{ComponentLifecycle, ComponentMeta};
+    use super::super::{ComponentLifecycle, ComponentMeta, RegistryTable};
 
     type GenericComponentMeta = ComponentMeta<Box<dyn Component>>;
 
@@ -262,11 +1212,19 @@ mod tests {
         fn meta() -> ComponentMeta<Box<A>> {
             ComponentMeta {
                 type_id: TypeId::of::<Injected<A>>(),
+                name: "A",
                 build: Box::new(|_| Box::new(A{})),
                 depends_on: vec![
                     TypeId::of::<Injected<B>>(),
                     TypeId::of::<Injected<C>>(),
-                ]
+                ],
+                requires: vec![
+                    (TypeId::of::<Injected<B>>(), "B"),
+                    (TypeId::of::<Injected<C>>(), "C"),
+                ],
+                scope: ComponentScope::Singleton,
+                profiles: &[],
+                enabled_if: None
             }
         }
     }
@@ -277,10 +1235,17 @@ mod tests {
         fn meta() -> ComponentMeta<Box<B>> {
             ComponentMeta {
                 type_id: TypeId::of::<Injected<B>>(),
+                name: "B",
                 build: Box::new(|_| Box::new(B{})),
                 depends_on: vec![
                     TypeId::of::<Injected<C>>(),
-                ]
+                ],
+                requires: vec![
+                    (TypeId::of::<Injected<C>>(), "C"),
+                ],
+                scope: ComponentScope::Singleton,
+                profiles: &[],
+                enabled_if: None
             }
         }
     }
@@ -295,8 +1260,13 @@ mod tests {
         fn meta() -> ComponentMeta<Box<C>> {
             ComponentMeta {
                 type_id: TypeId::of::<Injected<C>>(),
+                name: "C",
                 build: Box::new(|r: &ComponentRepository| Box::new(C::build(r))),
-                depends_on: vec![ ]
+                depends_on: vec![ ],
+                requires: vec![ ],
+                scope: ComponentScope::Singleton,
+                profiles: &[],
+                enabled_if: None
             }
         }
     }
@@ -313,24 +1283,18 @@ mod tests {
     }
 
     struct DemoRegistry { }
+    impl DemoRegistry {
+        fn table () -> &'static RegistryTable {
+            static TABLE: RegistryTable = RegistryTable::new();
+            TABLE.init(|| vec![A::meta().into(), B::meta().into(), C::meta().into()])
+        }
+    }
     impl ComponentRegistry for DemoRegistry {
-        fn get (tid: TypeId) -> Option<GenericComponentMeta> {
-            if tid == TypeId::of::<Injected<A>>() {
-                return Some(A::meta().into())
-            } else if tid == TypeId::of::<Injected<B>>() {
-                return Some(B::meta().into())
-            } else if tid == TypeId::of::<Injected<C>>() {
-                return Some(C::meta().into())
-            } else {
-                None
-            }
+        fn get (tid: TypeId) -> Option<&'static GenericComponentMeta> {
+            DemoRegistry::table().get(tid)
         }
         fn all () -> Vec<TypeId> {
-            vec![
-                TypeId::of::<Injected<A>>(),
-                TypeId::of::<Injected<B>>(),
-                TypeId::of::<Injected<C>>(),
-            ]
+            DemoRegistry::table().all()
         }
 
         fn new() -> Self {
@@ -354,6 +1318,24 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_topological_sort_from_registry() {
+
+        let registry = Registry::builder()
+            .register::<B>()
+            .register::<C>()
+            .build();
+        let sys = System::from_registry(registry);
+        let result = sys.topological_sort();
+        assert_eq!(
+            result,
+            vec![
+                TypeId::of::<Injected<C>>(),
+                TypeId::of::<Injected<B>>(),
+            ]
+        );
+    }
+
     #[tokio::test]
     async fn test_system_start_stop() {
 
@@ -0,0 +1,162 @@
+use std::any::TypeId;
+use std::marker::PhantomData;
+use std::sync::Arc;
+use std::time::Instant;
+use super::{Component, Injected};
+use super::system::{stop_component, StartedComponent, StopReport, DEFAULT_STOP_TIMEOUT};
+
+/// A macro that generates a statically typed system: a struct with an `Injected<T>` field for
+/// each component.
+///
+/// ```
+/// use sai::{Component, Injected};
+///
+/// #[derive(Component)]
+/// struct Db {}
+///
+/// #[derive(Component)]
+/// struct UserRepo {
+///     #[injected]
+///     db: Injected<Db>
+/// }
+///
+/// sai::system!(pub struct AppSystem {
+///     db: Db,
+///     users: UserRepo
+/// });
+///
+/// # async fn run() {
+/// let system = AppSystem::start().await;
+/// let users: &Injected<UserRepo> = &system.users;
+/// system.stop().await;
+/// # }
+/// ```
+///
+/// Components are started in the order of the fields, so every component has to be listed
+/// after its dependencies. Dependencies are resolved by type when the struct is compiled, so
+/// a missing dependency or a wrong order is a type error, and no downcasting is involved.
+///
+/// `stop` stops the components in the reversed order, like `System::stop`.
+///
+/// Every component is created once. Components with a custom constructor, `Lazy` or
+/// `Deferred` dependencies, or a `SystemHandle` are not supported, use a
+/// [System](struct.System.html) for those. A typed system has no status to report or requests
+/// to handle, so injecting a `SystemHandle` fails to compile.
+#[macro_export]
+macro_rules! system {
+    ($vis:vis struct $name:ident { $($field:ident : $ty:ty),* $(,)? }) => {
+
+        $vis struct $name {
+            $(
+                pub $field: $crate::Injected<$ty>,
+            )*
+        }
+
+        impl $name {
+            /// Build and start all components
+            pub async fn start() -> Self {
+                // Every started component, for the ones listed after it
+                let context = ();
+                $(
+                    let $field: $crate::Injected<$ty> = $crate::start_wired(&context).await;
+                    let context = ($field.clone(), context);
+                )*
+                drop(context);
+
+                $(
+                    $crate::ComponentLifecycle::post_start(&**$field).await;
+                )*
+
+                $name {
+                    $($field),*
+                }
+            }
+
+            /// Stop all components in the reversed order of the start
+            pub async fn stop(self) -> $crate::StopReport {
+                let $name { $($field),* } = self;
+                $crate::stop_wired(vec![
+                    $($crate::erase_wired($field)),*
+                ]).await
+            }
+        }
+
+    }
+}
+
+/// Implemented by `#[derive(Component)]`: build the component from the components in
+/// `Context`. `Indices` are the positions of its dependencies, which are inferred.
+#[doc(hidden)]
+pub trait Wire<Context, Indices> {
+    fn wire(context: &Context) -> Self;
+}
+
+#[doc(hidden)]
+pub trait WiredDependency<Context, Index> {
+    fn wire(context: &Context) -> Self;
+}
+
+impl<C, I, T: ?Sized> WiredDependency<C, I> for Injected<T> where C: Pick<Injected<T>, I> {
+    fn wire(context: &C) -> Self {
+        context.pick().clone()
+    }
+}
+
+/*
+ * The context of a typed system is a list of started components, e.g.
+ * `(Injected<B>, (Injected<A>, ()))`. Picking a component by its type is resolved by the
+ * compiler, with `Here` and `There` as the index.
+ */
+#[doc(hidden)]
+pub trait Pick<T, Index> {
+    fn pick(&self) -> &T;
+}
+
+#[doc(hidden)]
+pub struct Here;
+
+#[doc(hidden)]
+pub struct There<I>(PhantomData<I>);
+
+impl<T, Tail> Pick<T, Here> for (T, Tail) {
+    fn pick(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T, Head, Tail, I> Pick<T, There<I>> for (Head, Tail) where Tail: Pick<T, I> {
+    fn pick(&self) -> &T {
+        self.1.pick()
+    }
+}
+
+#[doc(hidden)]
+pub async fn start_wired<C, Context, Indices>(context: &Context) -> Injected<C>
+    where C: Component + Wire<Context, Indices>
+{
+    let mut component = C::wire(context);
+    component.start().await;
+    Injected::new(component)
+}
+
+#[doc(hidden)]
+pub fn erase_wired<C: Component + 'static>(component: Injected<C>) -> (TypeId, &'static str, Injected<dyn Component>) {
+    let component: Arc<dyn Component> = (*component).clone();
+    (TypeId::of::<Injected<C>>(), std::any::type_name::<C>(), Injected::from(component))
+}
+
+#[doc(hidden)]
+pub async fn stop_wired(mut components: Vec<(TypeId, &'static str, Injected<dyn Component>)>) -> StopReport {
+    let mut report = StopReport::default();
+
+    for (_, _, component) in components.iter().rev() {
+        component.pre_stop().await;
+    }
+
+    let deadline = Instant::now() + DEFAULT_STOP_TIMEOUT;
+    while let Some((type_id, name, component)) = components.pop() {
+        stop_component(StartedComponent { type_id, name, component }, deadline, &mut report).await;
+    }
+
+    report
+}
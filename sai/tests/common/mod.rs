@@ -0,0 +1,46 @@
+/*
+ * Fixtures shared by the tests. Not every test uses all of them.
+ */
+#![allow(dead_code, unused_macros)]
+
+use std::cell::RefCell;
+
+thread_local! {
+    static EVENTS: RefCell<Vec<&'static str>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Record an event, e.g. a lifecycle hook being called, on the current thread
+pub fn record(event: &'static str) {
+    EVENTS.with(|events| events.borrow_mut().push(event));
+}
+
+/// The events recorded on the current thread since the last call
+pub fn events() -> Vec<&'static str> {
+    EVENTS.with(|events| events.replace(Vec::new()))
+}
+
+/// A component that records "start <label>" and "stop <label>", injected with the given
+/// dependencies
+macro_rules! restartable {
+    ($name:ident, $label:expr $(, $field:ident : $dep:ty)*) => {
+        #[derive(Component)]
+        #[lifecycle]
+        #[allow(dead_code)] // The fields are only there to be injected
+        struct $name {
+            $(
+                #[injected]
+                $field: Injected<$dep>,
+            )*
+        }
+
+        #[async_trait]
+        impl ComponentLifecycle for $name {
+            async fn start (&mut self) {
+                $crate::common::record(concat!("start ", $label));
+            }
+            async fn stop (&mut self) {
+                $crate::common::record(concat!("stop ", $label));
+            }
+        }
+    }
+}
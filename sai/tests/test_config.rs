@@ -0,0 +1,90 @@
+use sai::{Component, DisabledComponent, Injected, System, ValidationError, component_registry};
+use std::collections::HashMap;
+
+#[derive(Component)]
+#[component(enabled_if = "features.search")]
+struct SearchIndex {}
+
+#[derive(Component)]
+struct Api {}
+
+component_registry!(AppRegistry, [SearchIndex, Api]);
+
+#[derive(Component)]
+#[allow(dead_code)]
+struct SearchApi {
+    #[injected]
+    index: Injected<SearchIndex>
+}
+
+component_registry!(SearchRegistry, [SearchIndex, SearchApi]);
+
+fn config(search: bool) -> HashMap<String, bool> {
+    let mut config = HashMap::new();
+    config.insert("features.search".to_string(), search);
+    config
+}
+
+#[tokio::test]
+async fn config_enables_components() {
+    let enabled = config(true);
+    let mut system: System<AppRegistry> = System::new()
+        .with_config(|key| enabled.get(key).cloned().unwrap_or(false));
+    assert!(system.disabled_components().is_empty());
+    system.start().await;
+    assert!(system.get::<SearchIndex>().is_ok());
+    system.stop().await.assert_no_leaks();
+
+    let disabled = config(false);
+    let mut system: System<AppRegistry> = System::new()
+        .with_config(|key| disabled.get(key).cloned().unwrap_or(false));
+    assert_eq!(
+        system.disabled_components(),
+        vec![DisabledComponent {
+            name: std::any::type_name::<SearchIndex>(),
+            condition: "features.search"
+        }]
+    );
+    system.start().await;
+    assert!(system.get::<SearchIndex>().is_err());
+    assert!(system.get::<Api>().is_ok());
+    system.stop().await.assert_no_leaks();
+}
+
+#[test]
+fn dependents_of_disabled_components_fail_validation() {
+    let system: System<SearchRegistry> = System::new().with_config(|_| false);
+    let errors = system.validate().unwrap_err();
+    assert_eq!(
+        errors,
+        vec![ValidationError::Disabled {
+            component: std::any::type_name::<SearchApi>(),
+            dependency: std::any::type_name::<SearchIndex>(),
+            condition: "features.search"
+        }]
+    );
+    assert_eq!(
+        errors[0].to_string(),
+        format!(
+            "{} depends on {}, which is disabled because `features.search` is not enabled",
+            std::any::type_name::<SearchApi>(),
+            std::any::type_name::<SearchIndex>()
+        )
+    );
+
+    let system: System<SearchRegistry> = System::new().with_config(|_| true);
+    assert_eq!(system.validate(), Ok(()));
+}
+
+#[test]
+fn registry_builder_evaluates_config_on_build() {
+    use sai::Registry;
+
+    // Registered after the configuration
+    let registry = Registry::builder()
+        .config(|key| key == "features.search")
+        .registry::<AppRegistry>()
+        .build();
+    assert_eq!(registry.len(), 2);
+    assert!(registry.disabled().is_empty());
+}
@@ -0,0 +1,76 @@
+use sai::{Component, ComponentLifecycle, Deferred, Injected, System, async_trait, component_registry};
+use std::any::TypeId;
+use std::sync::Mutex;
+
+#[derive(Component)]
+struct EventBus {
+    #[injected]
+    handler: Injected<Handler>,
+
+    published: Mutex<Vec<String>>
+}
+
+impl EventBus {
+    fn publish(&self, event: &str) {
+        self.published.lock().unwrap().push(event.to_string());
+    }
+}
+
+#[derive(Component)]
+#[lifecycle]
+struct Handler {
+    #[injected(deferred)]
+    bus: Deferred<Injected<EventBus>>
+}
+
+#[async_trait]
+impl ComponentLifecycle for Handler {
+    async fn start (&mut self) {
+        // The bus depends on this handler, so it's not built yet
+        assert!(self.bus.try_get().is_none());
+    }
+    async fn post_start (&self) {
+        self.bus.get().publish("handler ready");
+    }
+}
+
+component_registry!(BusRegistry, [EventBus, Handler]);
+
+#[tokio::test]
+async fn deferred_dependencies_allow_cycles() {
+    let mut system: System<BusRegistry> = System::new();
+    system.start().await;
+
+    let bus = system.get::<EventBus>().unwrap();
+    assert_eq!(*bus.published.lock().unwrap(), vec!["handler ready".to_string()]);
+    assert!(std::sync::Arc::ptr_eq(&bus.handler.bus.get(), &bus));
+    drop(bus);
+
+    system.stop().await.assert_no_leaks();
+}
+
+/*
+ * Cycles without a deferred side are still an error
+ */
+#[derive(Component)]
+#[allow(dead_code)]
+struct Ping {
+    #[injected]
+    pong: Injected<Pong>
+}
+
+#[derive(Component)]
+#[allow(dead_code)]
+struct Pong {
+    #[injected]
+    ping: Injected<Ping>
+}
+
+component_registry!(CycleRegistry, [Ping, Pong]);
+
+#[tokio::test]
+#[should_panic(expected = "circular dependency")]
+async fn cycles_are_still_detected() {
+    let mut system: System<CycleRegistry> = System::with_entrypoint(TypeId::of::<Injected<Ping>>());
+    system.start().await;
+}
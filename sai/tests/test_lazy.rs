@@ -0,0 +1,100 @@
+use sai::{Component, ComponentLifecycle, GetError, Injected, Lazy, Registry, System, ValidationError, async_trait, component_registry};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::any::TypeId;
+
+static DB_STARTED: AtomicUsize = AtomicUsize::new(0);
+static DB_STOPPED: AtomicUsize = AtomicUsize::new(0);
+static GENERATOR_STARTED: AtomicUsize = AtomicUsize::new(0);
+static GENERATOR_POST_STARTED: AtomicUsize = AtomicUsize::new(0);
+static GENERATOR_STOPPED: AtomicUsize = AtomicUsize::new(0);
+
+/*
+ * Only used by the lazy component
+ */
+#[derive(Component)]
+#[lifecycle]
+struct ReportDb {}
+
+#[async_trait]
+impl ComponentLifecycle for ReportDb {
+    async fn start (&mut self) {
+        DB_STARTED.fetch_add(1, Ordering::SeqCst);
+    }
+    async fn stop (&mut self) {
+        DB_STOPPED.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+#[derive(Component)]
+#[component(lazy)]
+#[lifecycle]
+#[allow(dead_code)]
+struct ReportGenerator {
+    #[injected]
+    db: Injected<ReportDb>
+}
+
+#[async_trait]
+impl ComponentLifecycle for ReportGenerator {
+    async fn start (&mut self) {
+        GENERATOR_STARTED.fetch_add(1, Ordering::SeqCst);
+    }
+    async fn post_start (&self) {
+        GENERATOR_POST_STARTED.fetch_add(1, Ordering::SeqCst);
+    }
+    async fn stop (&mut self) {
+        GENERATOR_STOPPED.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+#[derive(Component)]
+struct Admin {
+    #[injected]
+    reports: Lazy<Injected<ReportGenerator>>
+}
+
+component_registry!(LazyRegistry, [ReportDb, ReportGenerator, Admin]);
+
+#[tokio::test]
+async fn lazy_components_start_on_first_use() {
+    let mut system: System<LazyRegistry> = System::new();
+    system.start().await;
+
+    assert_eq!(DB_STARTED.load(Ordering::SeqCst), 0);
+    assert_eq!(GENERATOR_STARTED.load(Ordering::SeqCst), 0);
+    assert!(matches!(system.get::<ReportGenerator>(), Err(GetError::NotFound(_))));
+    assert_eq!(
+        system.get_by_typeid(TypeId::of::<Injected<ReportGenerator>>()).err(),
+        Some(GetError::NotFound(std::any::type_name::<ReportGenerator>().to_string()))
+    );
+
+    {
+        let admin = system.get::<Admin>().unwrap();
+        let generator = admin.reports.get().await;
+        let again = admin.reports.get().await;
+        assert!(std::sync::Arc::ptr_eq(&generator, &again));
+    }
+
+    assert_eq!(DB_STARTED.load(Ordering::SeqCst), 1);
+    assert_eq!(GENERATOR_STARTED.load(Ordering::SeqCst), 1);
+    assert_eq!(GENERATOR_POST_STARTED.load(Ordering::SeqCst), 1);
+    assert!(system.get::<ReportGenerator>().is_ok());
+
+    system.stop().await.assert_no_leaks();
+
+    assert_eq!(GENERATOR_STOPPED.load(Ordering::SeqCst), 1);
+    assert_eq!(DB_STOPPED.load(Ordering::SeqCst), 1);
+}
+
+component_registry!(IncompleteRegistry, [ReportDb, Admin]);
+
+#[test]
+fn lazy_components_are_validated() {
+    assert_eq!(
+        Registry::of::<IncompleteRegistry>().validate(),
+        Err(vec![ValidationError::Missing {
+            component: std::any::type_name::<Admin>(),
+            dependency: std::any::type_name::<ReportGenerator>()
+        }])
+    );
+}
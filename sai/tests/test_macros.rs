@@ -46,3 +46,122 @@ fn test_meta() {
     assert_eq!(meta.type_id, TypeId::of::<Injected<Foo>>());
     assert_eq!(meta.depends_on, vec![TypeId::of::<Injected<Bar>>()]);
 }
+
+/*
+ * Generic components
+ */
+trait Entity: Send + Sync + 'static {
+    fn table() -> &'static str;
+}
+
+struct User {}
+impl Entity for User {
+    fn table() -> &'static str { "users" }
+}
+
+#[derive(Component)]
+#[allow(dead_code)]
+struct Repo<T: Entity> where T: Sized {
+    #[injected]
+    bar: Injected<Bar>,
+
+    entity: std::marker::PhantomData<T>
+}
+
+impl<T: Entity> Repo<T> {
+    fn table(&self) -> &'static str {
+        T::table()
+    }
+}
+
+#[derive(Component)]
+#[allow(dead_code)]
+struct UserService {
+    #[injected]
+    repo: Injected<Repo<User>>
+}
+
+sai::component_registry!(GenericRegistry, [Bar, Repo<User>, UserService]);
+
+#[test]
+fn test_generic_component() {
+    use sai::ComponentRegistry;
+
+    let meta = Repo::<User>::meta();
+    assert_eq!(meta.type_id, TypeId::of::<Injected<Repo<User>>>());
+    assert_eq!(meta.depends_on, vec![TypeId::of::<Injected<Bar>>()]);
+
+    assert_eq!(UserService::meta().depends_on, vec![TypeId::of::<Injected<Repo<User>>>()]);
+    assert!(GenericRegistry::get(TypeId::of::<Injected<Repo<User>>>()).is_some());
+
+    let mut repo = sai::ComponentRepository::new();
+    let bar: Box<dyn Component> = (Bar::meta().build)(&repo);
+    let bar: Injected<dyn Component> = bar.into();
+    repo.insert_with_typeid(Bar::meta().type_id, bar);
+    assert_eq!(Repo::<User>::build(&repo).table(), "users");
+}
+
+/*
+ * Custom constructors
+ */
+#[derive(Component)]
+#[component(constructor = "Client::new")]
+struct Client {
+    endpoint: String,
+    retries: u32
+}
+
+impl Client {
+    fn new(bar: Injected<Bar>, repo: Injected<Repo<User>>) -> Self {
+        Client {
+            endpoint: format!("{}/{}", bar.value, repo.table()),
+            retries: 3
+        }
+    }
+}
+
+#[test]
+fn test_constructor() {
+    assert_eq!(
+        Client::meta().depends_on,
+        vec![TypeId::of::<Injected<Bar>>(), TypeId::of::<Injected<Repo<User>>>()]
+    );
+
+    let mut repo = sai::ComponentRepository::new();
+    let bar: Box<dyn Component> = (Bar::meta().build)(&repo);
+    let bar: Injected<dyn Component> = bar.into();
+    repo.insert_with_typeid(Bar::meta().type_id, bar);
+    let user_repo: Box<dyn Component> = (Repo::<User>::meta().build)(&repo);
+    let user_repo: Injected<dyn Component> = user_repo.into();
+    repo.insert_with_typeid(Repo::<User>::meta().type_id, user_repo);
+
+    let client = Client::build(&repo);
+    assert_eq!(client.endpoint, "/users");
+    assert_eq!(client.retries, 3);
+}
+
+#[derive(Component)]
+struct Migrations {}
+
+// Generic components are referred to via an alias
+type UserRepo = Repo<User>;
+
+#[derive(Component)]
+#[component(after(Migrations, UserRepo))]
+#[allow(dead_code)]
+struct HttpServer {
+    #[injected]
+    bar: Injected<Bar>
+}
+
+#[test]
+fn test_after() {
+    assert_eq!(
+        HttpServer::meta().depends_on,
+        vec![
+            TypeId::of::<Injected<Bar>>(),
+            TypeId::of::<Injected<Migrations>>(),
+            TypeId::of::<Injected<Repo<User>>>()
+        ]
+    );
+}
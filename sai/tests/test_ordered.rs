@@ -0,0 +1,140 @@
+use sai::{Component, ComponentLifecycle, ComponentRegistry, Injected, Lazy, Registry, System, async_trait, component_registry};
+use std::any::TypeId;
+mod common;
+use common::{events, record};
+
+#[derive(Component)]
+#[lifecycle]
+struct Migrations {}
+
+#[async_trait]
+impl ComponentLifecycle for Migrations {
+    async fn start (&mut self) { record("migrations"); }
+}
+
+struct Pool {}
+
+#[sai::provider]
+async fn pool() -> Result<Pool, String> {
+    record("pool");
+    Ok(Pool {})
+}
+
+#[derive(Component)]
+#[lifecycle]
+#[component(after(Migrations))]
+struct UserRepo {
+    #[injected]
+    pool: Injected<Pool>
+}
+
+#[async_trait]
+impl ComponentLifecycle for UserRepo {
+    async fn start (&mut self) { record("user_repo"); }
+}
+
+#[derive(Component)]
+#[component(lazy)]
+struct Reports {
+    #[injected]
+    pool: Injected<Pool>
+}
+
+#[derive(Component)]
+#[lifecycle]
+struct HttpServer {
+    #[injected]
+    users: Injected<UserRepo>,
+
+    #[injected]
+    reports: Lazy<Injected<Reports>>
+}
+
+#[async_trait]
+impl ComponentLifecycle for HttpServer {
+    async fn start (&mut self) { record("http_server"); }
+}
+
+// Dependents are listed before their dependencies
+component_registry!(OrderedRegistry, [HttpServer, Reports, UserRepo, pool, Migrations], ordered);
+
+#[test]
+fn order_is_computed_by_the_macro() {
+    let order = OrderedRegistry::order().unwrap();
+    let position = |tid: TypeId| order.iter().position(|t| *t == tid).unwrap();
+
+    assert_eq!(order.len(), 5);
+    assert!(position(TypeId::of::<Injected<Pool>>()) < position(TypeId::of::<Injected<UserRepo>>()));
+    assert!(position(TypeId::of::<Injected<Migrations>>()) < position(TypeId::of::<Injected<UserRepo>>()));
+    assert!(position(TypeId::of::<Injected<UserRepo>>()) < position(TypeId::of::<Injected<HttpServer>>()));
+    assert!(position(TypeId::of::<Injected<Pool>>()) < position(TypeId::of::<Injected<Reports>>()));
+}
+
+#[tokio::test]
+async fn system_starts_in_the_computed_order() {
+    let mut system: System<OrderedRegistry> = System::new();
+    events();
+    system.start().await;
+
+    // The lazy component is not started
+    assert!(system.get::<Reports>().is_err());
+    {
+        let server = system.get::<HttpServer>().unwrap();
+        let reports = server.reports.get().await;
+        assert!(std::sync::Arc::ptr_eq(&server.users.pool, &reports.pool));
+    }
+    system.stop().await.assert_no_leaks();
+
+    // Components of the same depth start in the order they are listed
+    assert_eq!(events(), vec![
+        "pool",
+        "migrations",
+        "user_repo",
+        "http_server"
+    ]);
+}
+
+/*
+ * Without dependencies, the components start in the order they are listed
+ */
+#[derive(Component)]
+#[lifecycle]
+struct First {}
+
+#[async_trait]
+impl ComponentLifecycle for First {
+    async fn start (&mut self) { record("first"); }
+}
+
+#[derive(Component)]
+#[lifecycle]
+struct Second {}
+
+#[async_trait]
+impl ComponentLifecycle for Second {
+    async fn start (&mut self) { record("second"); }
+}
+
+component_registry!(SiblingRegistry, [Second, First], ordered);
+
+struct Args {}
+
+#[tokio::test]
+async fn builders_keep_the_computed_order() {
+    let systems = vec![
+        System::builder()
+            .registry::<SiblingRegistry>()
+            .instance(Args {})
+            .build(),
+        System::from_registry(Registry::builder()
+            .merge(Registry::builder().registry::<SiblingRegistry>())
+            .build())
+    ];
+
+    for mut system in systems {
+        events();
+        system.start().await;
+        system.stop().await.assert_no_leaks();
+        assert_eq!(events(), vec!["second", "first"]);
+    }
+}
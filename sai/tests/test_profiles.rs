@@ -0,0 +1,98 @@
+use sai::{Component, Injected, Registry, System, ValidationError, component_registry};
+
+trait Cache: Send + Sync {
+    fn backend(&self) -> &'static str;
+}
+
+struct MemoryCache {}
+impl Cache for MemoryCache {
+    fn backend(&self) -> &'static str { "memory" }
+}
+
+struct RedisCache {}
+impl Cache for RedisCache {
+    fn backend(&self) -> &'static str { "redis" }
+}
+
+#[sai::provider(profile = "dev", profile = "test")]
+async fn memory_cache() -> Box<dyn Cache> {
+    Box::new(MemoryCache {})
+}
+
+#[sai::provider(profile = "prod")]
+async fn redis_cache() -> Box<dyn Cache> {
+    Box::new(RedisCache {})
+}
+
+#[derive(Component)]
+struct Api {
+    #[injected]
+    cache: Injected<Box<dyn Cache>>
+}
+
+#[derive(Component)]
+#[component(profile = "dev")]
+struct DebugConsole {}
+
+component_registry!(ProfileRegistry, [memory_cache, redis_cache, Api, DebugConsole]);
+
+#[tokio::test]
+async fn profiles_select_components() {
+    for (profile, backend) in [("dev", "memory"), ("test", "memory"), ("prod", "redis")].iter() {
+        let mut system: System<ProfileRegistry> = System::new().with_profiles(&[profile]);
+        system.start().await;
+
+        let api = system.get::<Api>().unwrap();
+        assert_eq!(api.cache.backend(), *backend);
+        assert_eq!(system.get::<DebugConsole>().is_ok(), *profile == "dev");
+        drop(api);
+
+        system.stop().await.assert_no_leaks();
+    }
+}
+
+#[test]
+fn profiles_are_validated() {
+    let registry = Registry::of::<ProfileRegistry>();
+    assert_eq!(registry.len(), 1);
+    assert_eq!(
+        registry.validate(),
+        Err(vec![ValidationError::Inactive {
+            component: std::any::type_name::<Api>(),
+            dependency: "test_profiles::redis_cache",
+            profiles: &["prod"]
+        }])
+    );
+
+    let registry = registry.with_profiles(&["prod"]);
+    assert_eq!(registry.len(), 2);
+    assert_eq!(registry.validate(), Ok(()));
+}
+
+#[tokio::test]
+#[should_panic(expected = "which is only active in profiles")]
+async fn system_without_profile_fails_validation() {
+    let mut system: System<ProfileRegistry> = System::new();
+    system.start().await;
+}
+
+#[test]
+fn component_registry_in_profiles() {
+    use sai::{ComponentRegistry, Registrable};
+    let cache = <memory_cache as Registrable>::component_type_id();
+
+    // The cache is bound by both providers, but listed once
+    assert_eq!(
+        ProfileRegistry::all(),
+        vec![cache, <Api as Registrable>::component_type_id(), <DebugConsole as Registrable>::component_type_id()]
+    );
+
+    assert_eq!(ProfileRegistry::all_in_profiles(&["prod"]).len(), 2);
+    assert_eq!(ProfileRegistry::all_in_profiles(&["dev"]).len(), 3);
+    assert_eq!(ProfileRegistry::all_in_profiles(&[]), vec![<Api as Registrable>::component_type_id()]);
+
+    let name = |profile| ProfileRegistry::get_in_profiles(cache, &[profile]).map(|meta| meta.name);
+    assert_eq!(name("prod"), Some("test_profiles::redis_cache"));
+    assert_eq!(name("test"), Some("test_profiles::memory_cache"));
+    assert_eq!(name("staging"), None);
+}
@@ -0,0 +1,189 @@
+use sai::{System, Component, ComponentLifecycle, Injected, Registry, async_trait, component_registry};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+#[derive(Component)]
+struct DbConfig {
+    url: String
+}
+
+// A third-party type that can't derive Component
+struct Pool {
+    url: String
+}
+
+static POOL_CLOSED: AtomicBool = AtomicBool::new(false);
+static REPO_STARTED: AtomicBool = AtomicBool::new(false);
+
+#[sai::provider(teardown = "close_pool")]
+async fn pg_pool(config: Injected<DbConfig>) -> Result<Pool, std::io::Error> {
+    Ok(Pool {
+        url: format!("postgresql://{}", config.url)
+    })
+}
+
+async fn close_pool(pool: &Pool) {
+    assert_eq!(pool.url, "postgresql://");
+    POOL_CLOSED.store(true, Ordering::SeqCst);
+}
+
+#[sai::provider]
+async fn greeting() -> String {
+    "hello".to_string()
+}
+
+#[derive(Component)]
+#[lifecycle]
+struct Repo {
+    #[injected]
+    pool: Injected<Pool>,
+
+    #[injected]
+    greeting: Injected<String>
+}
+
+#[async_trait]
+impl ComponentLifecycle for Repo {
+    async fn start (&mut self) {
+        assert_eq!(self.pool.url, "postgresql://");
+        assert_eq!(self.greeting.as_str(), "hello");
+        REPO_STARTED.store(true, Ordering::SeqCst);
+    }
+}
+
+component_registry!(ProviderRegistry, [DbConfig, pg_pool, greeting, Repo]);
+
+#[tokio::test]
+async fn system_with_providers() {
+    let mut system: System<ProviderRegistry> = System::new();
+
+    system.start().await;
+    assert!(REPO_STARTED.load(Ordering::SeqCst));
+
+    system.stop().await;
+    assert!(POOL_CLOSED.load(Ordering::SeqCst));
+}
+
+struct Cache {}
+
+static CACHE_RELEASED: AtomicBool = AtomicBool::new(false);
+static CACHE_CLOSED_AFTER_RELEASE: AtomicBool = AtomicBool::new(false);
+
+#[sai::provider(teardown = "close_cache")]
+async fn cache() -> Cache {
+    Cache {}
+}
+
+async fn close_cache(_: &Cache) {
+    CACHE_CLOSED_AFTER_RELEASE.store(CACHE_RELEASED.load(Ordering::SeqCst), Ordering::SeqCst);
+}
+
+component_registry!(CacheRegistry, [cache]);
+
+#[tokio::test]
+async fn provided_values_are_waited_for() {
+    let mut system: System<CacheRegistry> = System::new();
+    system.start().await;
+
+    // Released by a request still in flight while the system stops
+    let cache = system.get::<Cache>().unwrap();
+    let request = std::thread::spawn(move || {
+        std::thread::sleep(std::time::Duration::from_millis(50));
+        CACHE_RELEASED.store(true, Ordering::SeqCst);
+        drop(cache);
+    });
+    let report = system.stop().await;
+    request.join().unwrap();
+
+    assert!(report.is_clean());
+    assert!(CACHE_CLOSED_AFTER_RELEASE.load(Ordering::SeqCst));
+
+    // Never released
+    let mut system: System<CacheRegistry> = System::new()
+        .with_stop_timeout(std::time::Duration::from_millis(50));
+    system.start().await;
+    let cache = system.get::<Cache>().unwrap();
+    let report = system.stop().await;
+
+    assert_eq!(report.shared_components().len(), 1);
+    assert_eq!(report.shared_components()[0].references, 1);
+    // The value outlives the system
+    assert_eq!(report.leaks().len(), 1);
+    assert_eq!(report.leaks()[0].references, 1);
+    drop(cache);
+    report.assert_no_leaks();
+}
+
+#[tokio::test]
+async fn instances_are_reported_as_leaks() {
+    let mut system = System::builder()
+        .instance(DbConfig { url: "localhost".to_string() })
+        .stop_timeout(std::time::Duration::from_millis(50))
+        .build();
+    system.start().await;
+    let report = system.stop().await;
+    report.assert_no_leaks();
+
+    system.start().await;
+    let config = system.get::<DbConfig>().unwrap();
+    let report = system.stop().await;
+    drop(system);
+    assert_eq!(report.leaks().len(), 1);
+    assert_eq!(report.leaks()[0].references, 1);
+    assert_eq!(config.url, "localhost");
+}
+
+#[tokio::test]
+async fn instances_are_waited_for() {
+    let mut system = System::builder()
+        .instance(DbConfig { url: "localhost".to_string() })
+        .stop_timeout(std::time::Duration::from_millis(500))
+        .build();
+
+    // The registry keeps the instance, which doesn't hold up stopping
+    system.start().await;
+    let stopping = std::time::Instant::now();
+    assert!(system.stop().await.is_clean());
+    assert!(stopping.elapsed() < std::time::Duration::from_millis(400));
+
+    system.start().await;
+    let config = system.get::<DbConfig>().unwrap();
+    let report = system.stop().await;
+    assert_eq!(report.shared_components().len(), 1);
+    assert_eq!(report.shared_components()[0].references, 1);
+    assert_eq!(config.url, "localhost");
+}
+
+#[test]
+fn register_providers() {
+    let registry = Registry::builder()
+        .register::<DbConfig>()
+        .register::<pg_pool>()
+        .build();
+
+    let meta = registry.get(std::any::TypeId::of::<Injected<Pool>>()).unwrap();
+    assert_eq!(meta.depends_on, vec![std::any::TypeId::of::<Injected<DbConfig>>()]);
+}
+
+/*
+ * Providers returning an alias of `Result`
+ */
+mod db {
+    pub struct Pool {}
+
+    pub type Result<T> = std::result::Result<T, std::io::Error>;
+}
+
+#[sai::provider]
+async fn replica_pool() -> db::Result<db::Pool> {
+    Ok(db::Pool {})
+}
+
+component_registry!(AliasRegistry, [replica_pool]);
+
+#[tokio::test]
+async fn provider_returning_a_result_alias() {
+    let mut system: System<AliasRegistry> = System::new();
+    system.start().await;
+    assert!(system.get::<db::Pool>().is_ok());
+    system.stop().await.assert_no_leaks();
+}
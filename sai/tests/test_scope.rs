@@ -0,0 +1,227 @@
+use sai::{System, Component, ComponentLifecycle, Injected, Registry, SharedComponent, async_trait, component_registry};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+#[derive(Component)]
+struct Db {}
+
+static CONTEXT_STARTED: AtomicUsize = AtomicUsize::new(0);
+static CONTEXT_STOPPED: AtomicUsize = AtomicUsize::new(0);
+
+#[derive(Component)]
+#[lifecycle]
+struct RequestContext {
+    #[injected]
+    db: Injected<Db>
+}
+
+#[async_trait]
+impl ComponentLifecycle for RequestContext {
+    async fn start (&mut self) {
+        CONTEXT_STARTED.fetch_add(1, Ordering::SeqCst);
+    }
+    async fn stop (&mut self) {
+        CONTEXT_STOPPED.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+#[derive(Component)]
+struct RequestHandler {
+    #[injected]
+    context: Injected<RequestContext>,
+
+    #[injected]
+    db: Injected<Db>
+}
+
+component_registry!(RootRegistry, [Db]);
+
+fn scoped_registry() -> Registry {
+    Registry::builder()
+        .register::<RequestHandler>()
+        .register::<RequestContext>()
+        .build()
+}
+
+#[tokio::test]
+async fn scope_builds_components_on_demand() {
+    let mut system: System<RootRegistry> = System::new()
+        .with_scoped_registry(scoped_registry());
+    system.start().await;
+
+    {
+        let mut scope = system.scope();
+        let handler = scope.get::<RequestHandler>().await.unwrap();
+        let context = scope.get::<RequestContext>().await.unwrap();
+
+        // Shared within the scope
+        assert!(std::sync::Arc::ptr_eq(&handler.context, &context));
+        // Singletons come from the parent system
+        assert!(std::sync::Arc::ptr_eq(&handler.db, &context.db));
+        assert!(scope.get::<Db>().await.is_some());
+
+        drop(handler);
+        drop(context);
+        scope.end().await.assert_no_leaks();
+    }
+    assert_eq!(CONTEXT_STARTED.load(Ordering::SeqCst), 1);
+    assert_eq!(CONTEXT_STOPPED.load(Ordering::SeqCst), 1);
+
+    {
+        // Stopped when dropped
+        let mut scope = system.scope();
+        scope.get::<RequestContext>().await.unwrap();
+    }
+    assert_eq!(CONTEXT_STARTED.load(Ordering::SeqCst), 2);
+    assert_eq!(CONTEXT_STOPPED.load(Ordering::SeqCst), 2);
+
+    system.stop().await;
+}
+
+static SHARED_CONTEXT_STOPPED: AtomicUsize = AtomicUsize::new(0);
+
+#[derive(Component)]
+#[lifecycle]
+struct SharedContext {}
+
+#[async_trait]
+impl ComponentLifecycle for SharedContext {
+    async fn stop_shared (&self) {
+        SHARED_CONTEXT_STOPPED.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+#[tokio::test]
+async fn scope_ends_with_shared_components() {
+    let mut system: System<RootRegistry> = System::new()
+        .with_scoped_registry(Registry::builder().register::<SharedContext>().build())
+        .with_stop_timeout(Duration::from_millis(50));
+    system.start().await;
+
+    let mut scope = system.scope();
+    // Still held by a request handler when the scope ends
+    let context = scope.get::<SharedContext>().await.unwrap();
+    let report = scope.end().await;
+
+    assert_eq!(SHARED_CONTEXT_STOPPED.load(Ordering::SeqCst), 1);
+    assert_eq!(
+        report.shared_components(),
+        &[SharedComponent { name: std::any::type_name::<SharedContext>(), references: 1 }]
+    );
+    drop(context);
+    report.assert_no_leaks();
+
+    system.stop().await;
+}
+
+static CONNECTIONS_STOPPED: AtomicUsize = AtomicUsize::new(0);
+
+#[derive(Component)]
+#[component(scope = "transient")]
+#[lifecycle]
+struct Connection {}
+
+#[async_trait]
+impl ComponentLifecycle for Connection {
+    async fn stop (&mut self) {
+        CONNECTIONS_STOPPED.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+#[derive(Component)]
+#[component(lazy)]
+struct Reports {}
+
+#[derive(Component)]
+#[component(scope = "transient")]
+struct Buffer {}
+
+#[derive(Component)]
+struct Reader {
+    #[injected]
+    connection: Injected<Connection>,
+
+    #[injected]
+    buffer: Injected<Buffer>,
+
+    #[injected]
+    reports: Injected<Reports>
+}
+
+#[derive(Component)]
+struct Writer {
+    #[injected]
+    connection: Injected<Connection>,
+
+    #[injected]
+    buffer: Injected<Buffer>
+}
+
+component_registry!(PoolRegistry, [Db, Connection, Reports]);
+
+#[tokio::test]
+async fn scope_builds_transient_and_lazy_dependencies() {
+    let mut system: System<PoolRegistry> = System::new()
+        .with_scoped_registry(Registry::builder()
+            .register::<Reader>()
+            .register::<Writer>()
+            .register::<Buffer>()
+            .build());
+    system.start().await;
+    assert!(system.get::<Reports>().is_err());
+
+    let mut scope = system.scope();
+    let reader = scope.get::<Reader>().await.unwrap();
+    let writer = scope.get::<Writer>().await.unwrap();
+
+    // A transient instance per dependent, whether it's scoped or from the parent system
+    assert!(!std::sync::Arc::ptr_eq(&reader.connection, &writer.connection));
+    assert!(!std::sync::Arc::ptr_eq(&reader.buffer, &writer.buffer));
+    let buffer = scope.get::<Buffer>().await.unwrap();
+    assert!(!std::sync::Arc::ptr_eq(&reader.buffer, &buffer));
+
+    // The lazy component is started as a singleton of the parent system
+    assert!(std::sync::Arc::ptr_eq(&reader.reports, &system.get::<Reports>().unwrap()));
+
+    drop((reader, writer, buffer));
+    scope.end().await.assert_no_leaks();
+    assert_eq!(CONNECTIONS_STOPPED.load(Ordering::SeqCst), 2);
+
+    system.stop().await.assert_no_leaks();
+}
+
+#[tokio::test]
+async fn scope_can_be_moved_into_a_task() {
+    let mut system: System<RootRegistry> = System::new()
+        .with_scoped_registry(scoped_registry());
+    system.start().await;
+
+    let scope = system.scope();
+    let handled = tokio::spawn(async move {
+        let mut scope = scope;
+        let handler = scope.get::<RequestHandler>().await.unwrap();
+        drop(handler);
+        scope.end().await
+    });
+    handled.await.unwrap().assert_no_leaks();
+
+    system.stop().await.assert_no_leaks();
+}
+
+#[test]
+fn scoped_registry_is_validated() {
+    // The context requires Db from the system
+    let system: System<RootRegistry> = System::new()
+        .with_scoped_registry(scoped_registry());
+    assert_eq!(system.validate(), Ok(()));
+
+    let system: System<RootRegistry> = System::new()
+        .with_scoped_registry(Registry::builder().register::<RequestHandler>().build());
+    assert_eq!(
+        system.validate(),
+        Err(vec![sai::ValidationError::Missing {
+            component: std::any::type_name::<RequestHandler>(),
+            dependency: std::any::type_name::<RequestContext>()
+        }])
+    );
+}
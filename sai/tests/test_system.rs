@@ -1,4 +1,7 @@
 use sai::{System, Component, ComponentLifecycle, Injected, async_trait, component_registry};
+#[macro_use]
+mod common;
+use common::{events, record};
 use std::sync::Mutex;
 
 #[derive(Component)]
@@ -57,3 +60,629 @@ async fn system_integration_basic() {
     system.start().await;
     system.stop().await;
 }
+
+#[tokio::test]
+async fn system_stops_without_leaks() {
+    let mut system: System<SystemRegistry> = System::new();
+
+    system.start().await;
+    {
+        let a = system.get::<A>().unwrap();
+        assert_eq!(a.b.value, Some(2));
+    }
+    system.stop().await.assert_no_leaks();
+}
+
+/*
+ * Transient components
+ */
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+static TRANSIENT_STARTED: AtomicUsize = AtomicUsize::new(0);
+static TRANSIENT_STOPPED: AtomicUsize = AtomicUsize::new(0);
+
+#[derive(Component)]
+#[lifecycle]
+#[component(scope = "transient")]
+struct JobContext {
+    id: usize
+}
+
+#[async_trait]
+impl ComponentLifecycle for JobContext {
+    async fn start (&mut self) {
+        self.id = TRANSIENT_STARTED.fetch_add(1, Ordering::SeqCst);
+    }
+    async fn stop (&mut self) {
+        TRANSIENT_STOPPED.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+#[derive(Component)]
+#[allow(dead_code)]
+struct WorkerA {
+    #[injected]
+    context: Injected<JobContext>
+}
+
+#[derive(Component)]
+#[allow(dead_code)]
+struct WorkerB {
+    #[injected]
+    context: Injected<JobContext>,
+
+    #[injected]
+    worker_a: Injected<WorkerA>
+}
+
+component_registry!(TransientRegistry, [
+    JobContext, WorkerA, WorkerB
+]);
+
+#[tokio::test]
+async fn system_transient_components() {
+    let mut system: System<TransientRegistry> = System::new();
+
+    system.start().await;
+    assert_eq!(TRANSIENT_STARTED.load(Ordering::SeqCst), 2);
+
+    system.stop().await;
+    assert_eq!(TRANSIENT_STOPPED.load(Ordering::SeqCst), 2);
+}
+
+/*
+ * Instances
+ */
+struct Args {
+    name: String
+}
+
+static GREETER_STARTED: AtomicUsize = AtomicUsize::new(0);
+static GREETER_STOPPED: AtomicUsize = AtomicUsize::new(0);
+
+#[derive(Component)]
+#[lifecycle]
+struct Greeter {
+    greeting: String
+}
+
+#[async_trait]
+impl ComponentLifecycle for Greeter {
+    async fn start (&mut self) {
+        assert_eq!(self.greeting, "Hello");
+        GREETER_STARTED.fetch_add(1, Ordering::SeqCst);
+    }
+    async fn stop (&mut self) {
+        GREETER_STOPPED.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+#[derive(Component)]
+#[lifecycle]
+struct App {
+    #[injected]
+    args: Injected<Args>,
+
+    #[injected]
+    greeter: Injected<Greeter>,
+
+    message: Option<String>
+}
+
+#[async_trait]
+impl ComponentLifecycle for App {
+    async fn start (&mut self) {
+        self.message = Some(format!("{} {}", self.greeter.greeting, self.args.name));
+        assert_eq!(self.message.as_deref(), Some("Hello sai"));
+    }
+}
+
+component_registry!(AppRegistry, [App]);
+
+#[tokio::test]
+async fn system_with_instances() {
+    let mut system = System::builder()
+        .registry::<AppRegistry>()
+        .instance(Args { name: "sai".to_string() })
+        .managed_instance(Greeter { greeting: "Hello".to_string() })
+        .build();
+
+    system.start().await;
+    assert_eq!(GREETER_STARTED.load(Ordering::SeqCst), 1);
+
+    // The instance itself is injected, rather than a copy of it
+    {
+        let app = system.get::<App>().unwrap();
+        let args = system.get::<Args>().unwrap();
+        assert!(std::sync::Arc::ptr_eq(&app.args, &args));
+        assert_eq!(app.message.as_deref(), Some("Hello sai"));
+    }
+
+    system.stop().await;
+    assert_eq!(GREETER_STOPPED.load(Ordering::SeqCst), 1);
+}
+
+/*
+ * Access from outside
+ */
+use sai::GetError;
+
+trait Greet: Send + Sync {
+    fn greet(&self) -> String;
+}
+
+#[derive(Component)]
+struct FooController {
+    #[injected]
+    args: Injected<Args>
+}
+
+impl Greet for FooController {
+    fn greet(&self) -> String {
+        format!("Hi {}", self.args.name)
+    }
+}
+
+component_registry!(ControllerRegistry, [FooController]);
+
+#[tokio::test]
+async fn system_get_components() {
+    let mut system = System::builder()
+        .registry::<ControllerRegistry>()
+        .instance(Args { name: "sai".to_string() })
+        .build();
+
+    assert_eq!(system.get::<FooController>().err(), Some(GetError::NotStarted));
+
+    system.start().await;
+    {
+        let controller = system.get::<FooController>().unwrap();
+        assert_eq!(controller.greet(), "Hi sai");
+
+        let args = system.get::<Args>().unwrap();
+        assert_eq!(args.name, "sai");
+
+        let greet = system.get_as::<FooController, dyn Greet>(|c| c).unwrap();
+        assert_eq!(greet.greet(), "Hi sai");
+
+        assert!(matches!(system.get::<Greeter>(), Err(GetError::NotFound(_))));
+    }
+    system.stop().await;
+
+    assert_eq!(system.get::<FooController>().err(), Some(GetError::NotStarted));
+}
+
+/*
+ * Stopping shared components
+ */
+use sai::{Leak, SharedComponent};
+use std::time::Duration;
+
+static SERVER_STOPPED: AtomicUsize = AtomicUsize::new(0);
+static SERVER_STOPPED_SHARED: AtomicUsize = AtomicUsize::new(0);
+static WORKER_STOPPED: AtomicUsize = AtomicUsize::new(0);
+
+#[derive(Component)]
+#[lifecycle]
+struct Server {}
+
+#[async_trait]
+impl ComponentLifecycle for Server {
+    async fn stop (&mut self) {
+        SERVER_STOPPED.fetch_add(1, Ordering::SeqCst);
+    }
+    async fn stop_shared (&self) {
+        SERVER_STOPPED_SHARED.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+#[derive(Component)]
+#[lifecycle]
+struct Worker {}
+
+#[async_trait]
+impl ComponentLifecycle for Worker {
+    async fn stop (&mut self) {
+        WORKER_STOPPED.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+component_registry!(SharedRegistry, [Server, Worker]);
+
+#[tokio::test]
+async fn system_stop_shared_components() {
+    let mut system = System::builder()
+        .registry::<SharedRegistry>()
+        .stop_timeout(Duration::from_millis(200))
+        .build();
+    system.start().await;
+
+    // Dropped by the task shortly after stop begins
+    let worker = system.get::<Worker>().unwrap();
+    let task = std::thread::spawn(move || {
+        std::thread::sleep(Duration::from_millis(50));
+        drop(worker);
+    });
+
+    // Never dropped before the timeout
+    let server = system.get::<Server>().unwrap();
+
+    let report = system.stop().await;
+    task.join().unwrap();
+
+    assert_eq!(WORKER_STOPPED.load(Ordering::SeqCst), 1);
+    assert_eq!(SERVER_STOPPED.load(Ordering::SeqCst), 0);
+    assert_eq!(SERVER_STOPPED_SHARED.load(Ordering::SeqCst), 1);
+    assert!(!report.is_clean());
+    assert_eq!(
+        report.shared_components(),
+        &[SharedComponent { name: std::any::type_name::<Server>(), references: 1 }]
+    );
+
+    assert_eq!(report.leaks(), vec![Leak { name: std::any::type_name::<Server>(), references: 1 }]);
+    let leaked = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| report.assert_no_leaks()));
+    assert!(leaked.is_err());
+
+    drop(server);
+    assert!(report.leaks().is_empty());
+    report.assert_no_leaks();
+}
+
+/*
+ * Lifecycle phases
+ */
+#[derive(Component)]
+#[lifecycle]
+struct PhaseDb {}
+
+#[async_trait]
+impl ComponentLifecycle for PhaseDb {
+    async fn start (&mut self) { record("start db"); }
+    async fn post_start (&self) { record("post_start db"); }
+    async fn pre_stop (&self) { record("pre_stop db"); }
+    async fn stop (&mut self) { record("stop db"); }
+}
+
+#[derive(Component)]
+#[lifecycle]
+#[allow(dead_code)]
+struct PhaseHttp {
+    #[injected]
+    db: Injected<PhaseDb>
+}
+
+#[async_trait]
+impl ComponentLifecycle for PhaseHttp {
+    async fn start (&mut self) { record("start http"); }
+    async fn post_start (&self) { record("post_start http"); }
+    async fn pre_stop (&self) { record("pre_stop http"); }
+    async fn stop (&mut self) { record("stop http"); }
+}
+
+component_registry!(PhaseRegistry, [PhaseDb, PhaseHttp]);
+
+#[tokio::test]
+async fn system_lifecycle_phases() {
+    let mut system: System<PhaseRegistry> = System::new();
+    events();
+    system.start().await;
+    system.stop().await.assert_no_leaks();
+
+    assert_eq!(events(), vec![
+        "start db",
+        "start http",
+        "post_start db",
+        "post_start http",
+        "pre_stop http",
+        "pre_stop db",
+        "stop http",
+        "stop db"
+    ]);
+}
+
+/*
+ * Dropping a started system
+ */
+use sai::DropPolicy;
+
+static CONNECTION_STOPPED: AtomicUsize = AtomicUsize::new(0);
+
+#[derive(Component)]
+#[lifecycle]
+struct Connection {}
+
+#[async_trait]
+impl ComponentLifecycle for Connection {
+    async fn stop (&mut self) {
+        CONNECTION_STOPPED.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+component_registry!(ConnectionRegistry, [Connection]);
+
+#[tokio::test]
+async fn system_drop_policies() {
+    // Only warned about by default
+    let mut system: System<ConnectionRegistry> = System::new();
+    system.start().await;
+    drop(system);
+    assert_eq!(CONNECTION_STOPPED.load(Ordering::SeqCst), 0);
+
+    let mut system: System<ConnectionRegistry> = System::new()
+        .with_drop_policy(DropPolicy::Stop);
+    system.start().await;
+    drop(system);
+    assert_eq!(CONNECTION_STOPPED.load(Ordering::SeqCst), 1);
+
+    let mut system = System::builder()
+        .registry::<ConnectionRegistry>()
+        .drop_policy(DropPolicy::Panic)
+        .build();
+    system.start().await;
+    let dropped = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| drop(system)));
+    assert!(dropped.is_err());
+    assert_eq!(CONNECTION_STOPPED.load(Ordering::SeqCst), 1);
+
+    // Nothing to do for a stopped system
+    let mut system: System<ConnectionRegistry> = System::new()
+        .with_drop_policy(DropPolicy::Panic);
+    system.start().await;
+    system.stop().await;
+    drop(system);
+    assert_eq!(CONNECTION_STOPPED.load(Ordering::SeqCst), 2);
+}
+
+/*
+ * Status
+ */
+use sai::{ComponentState, ComponentStatus};
+
+#[derive(Component)]
+struct StatusDb {}
+
+#[derive(Component)]
+#[allow(dead_code)]
+struct StatusHttp {
+    #[injected]
+    db: Injected<StatusDb>
+}
+
+component_registry!(StatusRegistry, [StatusDb, StatusHttp]);
+
+fn states(status: &[ComponentStatus]) -> Vec<ComponentState> {
+    status.iter().map(|c| c.state).collect()
+}
+
+#[tokio::test]
+async fn system_status() {
+    let mut system: System<StatusRegistry> = System::new();
+    assert_eq!(states(&system.status()), vec![ComponentState::NotBuilt, ComponentState::NotBuilt]);
+
+    system.start().await;
+    let status = system.status();
+    assert_eq!(states(&status), vec![ComponentState::Started, ComponentState::Started]);
+    assert!(status.iter().all(|c| c.start_duration.is_some()));
+
+    let db = &status[0];
+    assert_eq!(db.name, std::any::type_name::<StatusDb>());
+    assert!(db.dependencies.is_empty());
+    assert_eq!(db.dependents, vec![std::any::type_name::<StatusHttp>()]);
+
+    let http = &status[1];
+    assert_eq!(http.dependencies, vec![std::any::type_name::<StatusDb>()]);
+    assert!(http.dependents.is_empty());
+
+    system.stop().await;
+    assert_eq!(states(&system.status()), vec![ComponentState::Stopped, ComponentState::Stopped]);
+}
+
+struct Broker {}
+
+#[sai::provider]
+async fn broker(_db: Injected<StatusDb>) -> Result<Broker, String> {
+    Err("connection refused".to_string())
+}
+
+component_registry!(FailingRegistry, [StatusDb, broker]);
+
+#[test]
+fn system_status_of_failed_component() {
+    let mut runtime = tokio::runtime::Runtime::new().unwrap();
+    let mut system: System<FailingRegistry> = System::new();
+
+    let started = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        runtime.block_on(system.start())
+    }));
+    assert!(started.is_err());
+
+    assert_eq!(states(&system.status()), vec![ComponentState::Started, ComponentState::Failed]);
+}
+
+/*
+ * SystemHandle
+ */
+use sai::SystemHandle;
+
+#[derive(Component)]
+#[lifecycle]
+struct Watchdog {
+    #[injected]
+    system: SystemHandle,
+
+    up_on_start: Option<bool>
+}
+
+#[async_trait]
+impl ComponentLifecycle for Watchdog {
+    async fn start(&mut self) {
+        self.up_on_start = Some(self.system.is_up());
+    }
+}
+
+component_registry!(WatchdogRegistry, [StatusDb, Watchdog]);
+
+#[tokio::test]
+async fn system_handle() {
+    let mut system: System<WatchdogRegistry> = System::new();
+    system.start().await;
+
+    let watchdog = system.get::<Watchdog>().unwrap();
+    assert_eq!(watchdog.up_on_start, Some(false));
+    assert!(watchdog.system.is_up());
+    assert_eq!(states(&watchdog.system.status()), vec![ComponentState::Started, ComponentState::Started]);
+
+    // Outlives the component
+    let handle = watchdog.system.clone();
+    drop(watchdog);
+
+    system.stop().await;
+    assert!(!handle.is_up());
+    assert_eq!(states(&handle.status()), vec![ComponentState::Stopped, ComponentState::Stopped]);
+}
+
+/*
+ * Restarts and requests
+ */
+use sai::RestartError;
+
+restartable!(RestartDb, "db");
+restartable!(RestartRepo, "repo", db: RestartDb);
+restartable!(RestartHttp, "http", repo: RestartRepo, context: RestartContext);
+restartable!(RestartJob, "job", db: RestartDb);
+
+#[derive(Component)]
+#[component(scope = "transient")]
+#[lifecycle]
+struct RestartContext {}
+
+#[async_trait]
+impl ComponentLifecycle for RestartContext {
+    async fn start (&mut self) {
+        record("start context");
+    }
+    async fn stop (&mut self) {
+        record("stop context");
+    }
+}
+
+component_registry!(RestartRegistry, [RestartDb, RestartRepo, RestartContext, RestartHttp, RestartJob]);
+
+#[tokio::test]
+async fn system_restart() {
+    let mut system: System<RestartRegistry> = System::new();
+    assert_eq!(system.restart::<RestartRepo>().await, Err(RestartError::NotStarted));
+
+    system.start().await;
+    events();
+    let job = system.get::<RestartJob>().unwrap();
+
+    let restarted = system.restart::<RestartRepo>().await.unwrap();
+    assert_eq!(restarted, vec![
+        std::any::type_name::<RestartRepo>(),
+        std::any::type_name::<RestartHttp>()
+    ]);
+    assert_eq!(events(), vec![
+        "stop http",
+        "stop context",
+        "stop repo",
+        "start repo",
+        "start context",
+        "start http"
+    ]);
+
+    // Other components are untouched
+    assert!(std::sync::Arc::ptr_eq(&*system.get::<RestartJob>().unwrap(), &*job));
+    assert!(system.status().iter().all(|c| c.state == ComponentState::Started));
+    drop(job);
+
+    assert_eq!(
+        system.restart::<RestartContext>().await,
+        Err(RestartError::Unsupported(std::any::type_name::<RestartContext>()))
+    );
+
+    system.stop().await.assert_no_leaks();
+    // Restarted components are stopped first, as they were started last
+    assert_eq!(events(), vec!["stop http", "stop context", "stop repo", "stop job", "stop db"]);
+}
+
+restartable!(SessionDb, "db");
+restartable!(SessionWorker, "worker", session: Session);
+
+#[derive(Component)]
+#[component(scope = "transient")]
+#[lifecycle]
+#[allow(dead_code)]
+struct Session {
+    #[injected]
+    db: Injected<SessionDb>
+}
+
+#[async_trait]
+impl ComponentLifecycle for Session {
+    async fn start (&mut self) { record("start session"); }
+    async fn stop (&mut self) { record("stop session"); }
+}
+
+component_registry!(SessionRegistry, [SessionDb, Session, SessionWorker]);
+
+#[tokio::test]
+async fn system_restart_through_transients() {
+    let mut system: System<SessionRegistry> = System::new();
+    system.start().await;
+    events();
+
+    // The worker only depends on the database through its transient session
+    let restarted = system.restart::<SessionDb>().await.unwrap();
+    assert_eq!(restarted, vec![
+        std::any::type_name::<SessionDb>(),
+        std::any::type_name::<SessionWorker>()
+    ]);
+    assert_eq!(events(), vec![
+        "stop worker",
+        "stop session",
+        "stop db",
+        "start db",
+        "start session",
+        "start worker"
+    ]);
+
+    assert_eq!(
+        system.restart_by_typeid(std::any::TypeId::of::<RestartDb>()).await,
+        Err(RestartError::NotFound(format!("{:?}", std::any::TypeId::of::<RestartDb>())))
+    );
+
+    system.stop().await.assert_no_leaks();
+}
+
+#[tokio::test]
+async fn system_handle_requests() {
+    let mut system: System<WatchdogRegistry> = System::new();
+    system.start().await;
+    let handle = system.get::<Watchdog>().unwrap().system.clone();
+
+    // Nothing handles requests yet
+    let db = system.get::<StatusDb>().unwrap();
+    assert_eq!(
+        handle.restart(std::any::type_name::<StatusDb>(), Duration::from_millis(10)),
+        Err(RestartError::Timeout)
+    );
+    handle.request_stop();
+    system.handle_requests().await;
+    // The request was withdrawn rather than handled late
+    assert!(std::sync::Arc::ptr_eq(&system.get::<StatusDb>().unwrap(), &db));
+    drop(db);
+
+    let operator = std::thread::spawn(move || {
+        let restarted = handle.restart(std::any::type_name::<StatusDb>(), Duration::from_secs(5));
+        let missing = handle.restart("NoSuchComponent", Duration::from_secs(5));
+        handle.request_stop();
+        (restarted, missing)
+    });
+    system.handle_requests().await;
+
+    let (restarted, missing) = operator.join().unwrap();
+    assert_eq!(restarted, Ok(vec![std::any::type_name::<StatusDb>()]));
+    assert_eq!(missing, Err(RestartError::NotFound("NoSuchComponent".to_string())));
+
+    system.stop().await.assert_no_leaks();
+}
@@ -0,0 +1,82 @@
+use sai::{Component, ComponentLifecycle, Injected, async_trait};
+mod common;
+use common::{events, record};
+
+#[derive(Component)]
+#[lifecycle]
+struct Db {
+    url: String
+}
+
+#[async_trait]
+impl ComponentLifecycle for Db {
+    async fn start (&mut self) {
+        self.url = "postgres://localhost".to_string();
+        record("start db");
+    }
+    async fn post_start (&self) { record("post_start db"); }
+    async fn pre_stop (&self) { record("pre_stop db"); }
+    async fn stop (&mut self) { record("stop db"); }
+}
+
+#[derive(Component)]
+struct Repo<T> {
+    #[injected]
+    db: Injected<Db>,
+    entity: std::marker::PhantomData<T>
+}
+
+struct User {}
+
+#[derive(Component)]
+#[lifecycle]
+struct HttpServer {
+    #[injected]
+    users: Injected<Repo<User>>,
+
+    #[injected]
+    db: Injected<Db>
+}
+
+#[async_trait]
+impl ComponentLifecycle for HttpServer {
+    async fn start (&mut self) { record("start http"); }
+    async fn post_start (&self) { record("post_start http"); }
+    async fn pre_stop (&self) { record("pre_stop http"); }
+    async fn stop (&mut self) { record("stop http"); }
+}
+
+// Doesn't use the database, but has to start after it
+#[derive(Component)]
+#[component(after(Db))]
+struct Metrics {}
+
+sai::system!(struct AppSystem {
+    db: Db,
+    metrics: Metrics,
+    users: Repo<User>,
+    http: HttpServer,
+});
+
+#[tokio::test]
+async fn typed_system_lifecycle() {
+    events();
+    let system = AppSystem::start().await;
+
+    assert_eq!(system.http.db.url, "postgres://localhost");
+    assert!(std::sync::Arc::ptr_eq(&system.users.db, &system.db));
+    assert!(std::sync::Arc::ptr_eq(&system.http.users, &system.users));
+
+    system.stop().await.assert_no_leaks();
+
+    assert_eq!(events(), vec![
+        "start db",
+        "start http",
+        "post_start db",
+        "post_start http",
+        "pre_stop http",
+        "pre_stop db",
+        "stop http",
+        "stop db"
+    ]);
+}
@@ -0,0 +1,7 @@
+use sai::Component;
+
+#[derive(Component)]
+#[component(after("Migrations"))]
+struct Foo {}
+
+fn main() {}
@@ -0,0 +1,7 @@
+use sai::Component;
+
+#[derive(Component)]
+#[component]
+struct Foo {}
+
+fn main() {}
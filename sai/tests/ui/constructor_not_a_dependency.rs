@@ -0,0 +1,15 @@
+use sai::Component;
+
+#[derive(Component)]
+#[component(constructor = "Foo::new")]
+struct Foo {
+    name: String
+}
+
+impl Foo {
+    fn new(name: String) -> Self {
+        Foo { name }
+    }
+}
+
+fn main() {}
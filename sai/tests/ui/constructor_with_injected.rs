@@ -0,0 +1,19 @@
+use sai::{Component, Injected};
+
+#[derive(Component)]
+struct Bar {}
+
+#[derive(Component)]
+#[component(constructor = "Foo::new")]
+struct Foo {
+    #[injected]
+    bar: Injected<Bar>
+}
+
+impl Foo {
+    fn new(bar: Injected<Bar>) -> Self {
+        Foo { bar }
+    }
+}
+
+fn main() {}
@@ -0,0 +1,12 @@
+use sai::{Component, Injected};
+
+#[derive(Component)]
+struct Bus {}
+
+#[derive(Component)]
+struct Handler {
+    #[injected(deferred)]
+    bus: Injected<Bus>
+}
+
+fn main() {}
@@ -0,0 +1,9 @@
+use sai::Component;
+
+#[derive(Component)]
+enum Foo {
+    A,
+    B
+}
+
+fn main() {}
@@ -0,0 +1,15 @@
+use sai::Component;
+
+#[derive(Component)]
+struct Bar {}
+
+#[derive(Component)]
+struct Foo {
+    #[injected]
+    bar: std::sync::Arc<Bar>,
+
+    #[injected]
+    baz: Bar
+}
+
+fn main() {}
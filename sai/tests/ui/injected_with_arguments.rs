@@ -0,0 +1,12 @@
+use sai::{Component, Injected};
+
+#[derive(Component)]
+struct Bar {}
+
+#[derive(Component)]
+struct Foo {
+    #[injected(bar)]
+    bar: Injected<Bar>
+}
+
+fn main() {}
@@ -0,0 +1,7 @@
+use sai::Component;
+
+#[derive(Component)]
+#[component(scope = "request")]
+struct Foo {}
+
+fn main() {}
@@ -0,0 +1,7 @@
+use sai::Component;
+
+#[derive(Component)]
+#[component(scope = "transient", lazy)]
+struct Foo {}
+
+fn main() {}
@@ -0,0 +1,7 @@
+use sai::Component;
+
+#[derive(Component)]
+#[lifecycle(start)]
+struct Foo {}
+
+fn main() {}
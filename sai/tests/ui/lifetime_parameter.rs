@@ -0,0 +1,8 @@
+use sai::Component;
+
+#[derive(Component)]
+struct Foo<'a> {
+    name: &'a str
+}
+
+fn main() {}
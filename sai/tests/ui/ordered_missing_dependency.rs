@@ -0,0 +1,14 @@
+use sai::{Component, Injected, component_registry};
+
+#[derive(Component)]
+struct Db {}
+
+#[derive(Component)]
+struct UserRepo {
+    #[injected]
+    db: Injected<Db>
+}
+
+component_registry!(RootRegistry, [UserRepo], ordered);
+
+fn main() {}
@@ -0,0 +1,6 @@
+#[sai::provider]
+fn greeting() -> String {
+    "hello".to_string()
+}
+
+fn main() {}
@@ -0,0 +1,11 @@
+struct Pool {}
+
+// The first type argument is not the provided type
+type Result<E> = std::result::Result<Pool, E>;
+
+#[sai::provider]
+async fn pool() -> Result<std::io::Error> {
+    Ok(Pool {})
+}
+
+fn main() {}
@@ -0,0 +1,4 @@
+#[sai::provider]
+async fn nothing() {}
+
+fn main() {}
@@ -0,0 +1,13 @@
+use sai::{Component, SystemHandle};
+
+#[derive(Component)]
+struct HealthCheck {
+    #[injected]
+    system: SystemHandle
+}
+
+sai::system!(struct AppSystem {
+    health: HealthCheck
+});
+
+fn main() {}
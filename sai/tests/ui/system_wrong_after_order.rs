@@ -0,0 +1,15 @@
+use sai::Component;
+
+#[derive(Component)]
+struct Migrations {}
+
+#[derive(Component)]
+#[component(after(Migrations))]
+struct Server {}
+
+sai::system!(struct AppSystem {
+    server: Server,
+    migrations: Migrations
+});
+
+fn main() {}
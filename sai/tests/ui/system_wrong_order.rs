@@ -0,0 +1,17 @@
+use sai::{Component, Injected};
+
+#[derive(Component)]
+struct Db {}
+
+#[derive(Component)]
+struct UserRepo {
+    #[injected]
+    db: Injected<Db>
+}
+
+sai::system!(struct AppSystem {
+    users: UserRepo,
+    db: Db
+});
+
+fn main() {}
@@ -0,0 +1,6 @@
+use sai::Component;
+
+#[derive(Component)]
+struct Foo(u32);
+
+fn main() {}
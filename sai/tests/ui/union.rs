@@ -0,0 +1,9 @@
+use sai::Component;
+
+#[derive(Component)]
+union Foo {
+    a: u32,
+    b: f32
+}
+
+fn main() {}
@@ -0,0 +1,7 @@
+use sai::Component;
+
+#[derive(Component)]
+#[component(colour = "red")]
+struct Foo {}
+
+fn main() {}
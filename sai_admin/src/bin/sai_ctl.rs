@@ -0,0 +1,51 @@
+//! Control a running Sai System through the socket of its `ControlServer`
+//!
+//! ```text
+//! sai-ctl [--socket <path>] status
+//! sai-ctl [--socket <path>] stop
+//! sai-ctl [--socket <path>] restart <component>
+//! ```
+//!
+//! The socket defaults to `$SAI_CTL_SOCKET`.
+
+#[cfg(unix)]
+fn main() {
+    use std::path::PathBuf;
+    use std::process;
+
+    let usage = "Usage: sai-ctl [--socket <path>] (status | stop | restart <component>)";
+
+    let mut args: Vec<String> = std::env::args().skip(1).collect();
+    let mut socket = std::env::var_os("SAI_CTL_SOCKET").map(PathBuf::from);
+    if args.len() >= 2 && args[0] == "--socket" {
+        socket = Some(PathBuf::from(args.remove(1)));
+        args.remove(0);
+    }
+
+    let socket = socket.unwrap_or_else(|| {
+        eprintln!("The socket is neither given by --socket nor $SAI_CTL_SOCKET\n{}", usage);
+        process::exit(2);
+    });
+    if args.is_empty() {
+        eprintln!("{}", usage);
+        process::exit(2);
+    }
+
+    match sai_admin::control::send(&socket, &args.join(" ")) {
+        Ok(Ok(output)) => print!("{}", output),
+        Ok(Err(message)) => {
+            eprintln!("{}", message);
+            process::exit(1);
+        },
+        Err(e) => {
+            eprintln!("Unable to connect to {}: {}", socket.display(), e);
+            process::exit(1);
+        }
+    }
+}
+
+#[cfg(not(unix))]
+fn main() {
+    eprintln!("sai-ctl is only supported on Unix");
+    std::process::exit(1);
+}
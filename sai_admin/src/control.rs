@@ -0,0 +1,316 @@
+//! A local control socket for operators, see [ControlServer](struct.ControlServer.html).
+//!
+//! The protocol is line based: the client sends one command, and the server replies with `ok`
+//! or `error` on the first line, followed by the output of the command, then closes the
+//! connection. The commands are:
+//!
+//! - `status`: the state, start duration and name of every component
+//! - `stop`: stop the system gracefully, see `SystemHandle::request_stop`
+//! - `restart <name>`: restart a component and its dependents, see `System::restart`. The name
+//!   can be shortened to its last segments, e.g. `Db` for `my_app::db::Db`.
+
+use std::fs::{self, DirBuilder};
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::os::unix::fs::{DirBuilderExt, FileTypeExt, PermissionsExt};
+use std::net::Shutdown;
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+use sai::{Component, ComponentLifecycle, ComponentStatus, Injected, SystemHandle, async_trait};
+use super::limit::ConnectionLimit;
+
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+/* The most that is read of a command */
+const MAX_COMMAND_SIZE: u64 = 1024;
+
+/* The most that is discarded of a command that was too long, before closing the connection */
+const MAX_DISCARD_SIZE: u64 = 16 * 1024;
+
+/// How many connections are served at once by default
+pub const DEFAULT_MAX_CONNECTIONS: usize = 8;
+
+/// How long a `restart` command waits for the System to handle it by default
+pub const DEFAULT_RESTART_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// The configuration of [ControlServer](struct.ControlServer.html), which has to be registered
+/// with the System, e.g. via `SystemBuilder::instance` or a provider.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ControlConfig {
+    /// Where to create the socket. A stale socket left by a previous process is replaced.
+    /// Only the owner of the process can connect to it.
+    pub path: PathBuf,
+    /// How long a `restart` command waits for the System to handle it
+    pub restart_timeout: Duration,
+    /// How many connections are served at once. Further connections are turned away with an
+    /// error until one of them is done.
+    pub max_connections: usize
+}
+
+impl ControlConfig {
+    pub fn new<P: Into<PathBuf>>(path: P) -> Self {
+        ControlConfig {
+            path: path.into(),
+            restart_timeout: DEFAULT_RESTART_TIMEOUT,
+            max_connections: DEFAULT_MAX_CONNECTIONS
+        }
+    }
+}
+
+/// A component that serves the control socket while the System is running.
+///
+/// Stop and restart requests are handled by `System::handle_requests`:
+/// ```ignore
+/// let mut system = System::builder()
+///     .registry::<RootRegistry>()
+///     .register::<ControlServer>()
+///     .instance(ControlConfig::new("/run/my-app/control.sock"))
+///     .build();
+///
+/// system.start().await;
+/// system.handle_requests().await; // Until `sai-ctl stop`
+/// system.stop().await;
+/// ```
+#[derive(Component)]
+#[lifecycle]
+pub struct ControlServer {
+    #[injected]
+    config: Injected<ControlConfig>,
+
+    #[injected]
+    system: SystemHandle,
+
+    server: Option<Server>
+}
+
+#[async_trait]
+impl ComponentLifecycle for ControlServer {
+    async fn start(&mut self) {
+        let path = self.config.path.clone();
+        let listener = bind(&path)
+            .unwrap_or_else(|e| panic!("Unable to listen on {}: {}", path.display(), e));
+
+        let stopping = Arc::new(AtomicBool::new(false));
+        let system = self.system.clone();
+        let thread = {
+            let stopping = stopping.clone();
+            let restart_timeout = self.config.restart_timeout;
+            let limit = ConnectionLimit::new(self.config.max_connections);
+            thread::spawn(move || serve(listener, system, restart_timeout, limit, stopping))
+        };
+
+        self.server = Some(Server {
+            path,
+            stopping,
+            thread
+        });
+    }
+
+    async fn stop(&mut self) {
+        if let Some(server) = self.server.take() {
+            server.shutdown();
+            let _ = server.thread.join();
+        }
+    }
+
+    async fn stop_shared(&self) {
+        if let Some(server) = &self.server {
+            server.shutdown();
+        }
+    }
+}
+
+struct Server {
+    path: PathBuf,
+    stopping: Arc<AtomicBool>,
+    thread: JoinHandle<()>
+}
+
+impl Server {
+    fn shutdown(&self) {
+        self.stopping.store(true, Ordering::SeqCst);
+
+        // Wake up the accepting thread, which checks the flag before serving a connection
+        let _ = UnixStream::connect(&self.path);
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+fn bind(path: &Path) -> io::Result<UnixListener> {
+    let existing = fs::symlink_metadata(path).ok().map(|metadata| metadata.file_type());
+    if let Some(file_type) = existing {
+        if !file_type.is_socket() {
+            return Err(io::Error::new(io::ErrorKind::AlreadyExists, "the path is not a socket"));
+        }
+        if UnixStream::connect(path).is_ok() {
+            return Err(io::Error::new(io::ErrorKind::AddrInUse, "the socket is in use"));
+        }
+        fs::remove_file(path)?;
+    }
+
+    // Commands are not authenticated, so the socket must not be open to other users, not even
+    // briefly: it's created in a private directory, and moved into place once restricted
+    let dir = private_dir(path)?;
+    let staged = dir.join("s");
+    let listener = UnixListener::bind(&staged)
+        .and_then(|listener| fs::set_permissions(&staged, fs::Permissions::from_mode(0o600)).map(|_| listener))
+        .and_then(|listener| fs::rename(&staged, path).map(|_| listener));
+    let _ = fs::remove_dir_all(&dir);
+    listener
+}
+
+/* A directory next to `path` that only the owner of the process can enter */
+fn private_dir(path: &Path) -> io::Result<PathBuf> {
+    let name = path
+        .file_name()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "the path has no file name"))?;
+    let dir = path.with_file_name(format!(".{}.{}", name.to_string_lossy(), std::process::id()));
+
+    let mut builder = DirBuilder::new();
+    builder.mode(0o700);
+    match builder.create(&dir) {
+        // Left behind by a previous process with the same id
+        Err(e) if e.kind() == io::ErrorKind::AlreadyExists => {
+            fs::remove_dir_all(&dir)?;
+            builder.create(&dir)?;
+        },
+        result => result?
+    }
+    Ok(dir)
+}
+
+fn serve(
+    listener: UnixListener,
+    system: SystemHandle,
+    restart_timeout: Duration,
+    limit: ConnectionLimit,
+    stopping: Arc<AtomicBool>
+) {
+    for stream in listener.incoming() {
+        if stopping.load(Ordering::SeqCst) {
+            return;
+        }
+
+        // A restart blocks until it's done, so each connection has a thread of its own
+        if let Ok(stream) = stream {
+            match limit.acquire() {
+                Some(permit) => {
+                    let system = system.clone();
+                    thread::spawn(move || {
+                        let _ = respond(stream, &system, restart_timeout);
+                        drop(permit);
+                    });
+                },
+                None => {
+                    // Without waiting for the client, which would hold up the other connections
+                    let _ = stream
+                        .set_nonblocking(true)
+                        .and_then(|_| reply(&stream, Err("Too many connections".to_string())))
+                        .and_then(|_| discard(&stream));
+                }
+            }
+        }
+    }
+}
+
+fn respond(stream: UnixStream, system: &SystemHandle, restart_timeout: Duration) -> io::Result<()> {
+    stream.set_read_timeout(Some(TIMEOUT))?;
+    stream.set_write_timeout(Some(TIMEOUT))?;
+
+    let mut command = String::new();
+    let mut reader = BufReader::new((&stream).take(MAX_COMMAND_SIZE));
+    reader.read_line(&mut command)?;
+    if !command.ends_with('\n') && reader.get_ref().limit() == 0 {
+        reply(&stream, Err("The command is too long".to_string()))?;
+        return discard(&stream);
+    }
+
+    let output = execute(command.trim(), system, restart_timeout);
+    reply(&stream, output)
+}
+
+fn reply(mut stream: &UnixStream, result: Result<String, String>) -> io::Result<()> {
+    match result {
+        Ok(output) => write!(stream, "ok\n{}", output)?,
+        Err(message) => write!(stream, "error\n{}\n", message)?
+    };
+    stream.flush()
+}
+
+/* Closing with unread input resets the connection, which may discard the reply */
+fn discard(stream: &UnixStream) -> io::Result<()> {
+    stream.shutdown(Shutdown::Write)?;
+    io::copy(&mut stream.take(MAX_DISCARD_SIZE), &mut io::sink()).map(|_| ())
+}
+
+fn execute(command: &str, system: &SystemHandle, restart_timeout: Duration) -> Result<String, String> {
+    let mut words = command.split_whitespace();
+    match (words.next(), words.next(), words.next()) {
+        (Some("status"), None, _) => Ok(status(&system.status())),
+        (Some("stop"), None, _) => {
+            system.request_stop();
+            Ok("Stopping\n".to_string())
+        },
+        (Some("restart"), Some(name), None) => {
+            let name = resolve(&system.status(), name)?;
+            let restarted = system.restart(name, restart_timeout).map_err(|e| e.to_string())?;
+            Ok(restarted.iter().map(|name| format!("Restarted {}\n", name)).collect())
+        },
+        _ => Err(format!("Unknown command: {}", command))
+    }
+}
+
+fn status(status: &[ComponentStatus]) -> String {
+    status
+        .iter()
+        .map(|component| {
+            let start_duration = match component.start_duration {
+                Some(duration) => format!("{:.1}ms", duration.as_secs_f64() * 1000.0),
+                None => "-".to_string()
+            };
+            format!("{:<10} {:>10}  {}\n", format!("{:?}", component.state), start_duration, component.name)
+        })
+        .collect()
+}
+
+/* The full name of the component that `name` refers to */
+fn resolve(status: &[ComponentStatus], name: &str) -> Result<&'static str, String> {
+    let suffix = format!("::{}", name);
+    let matches: Vec<&'static str> = status
+        .iter()
+        .map(|component| component.name)
+        .filter(|full| *full == name || full.ends_with(&suffix))
+        .collect();
+
+    match matches.as_slice() {
+        [] => Err(format!("{} is not a component of the system", name)),
+        [full] => Ok(full),
+        _ => Err(format!("{} is ambiguous: {}", name, matches.join(", ")))
+    }
+}
+
+/// Send a command to the control socket at `path`. Returns the output of the command, or the
+/// error reported by the server.
+pub fn send(path: &Path, command: &str) -> io::Result<Result<String, String>> {
+    let mut stream = UnixStream::connect(path)?;
+    let written = writeln!(stream, "{}", command);
+
+    // The server may reply and close without reading the command, e.g. when it's busy
+    let mut reply = String::new();
+    let read = stream.read_to_string(&mut reply);
+    if reply.is_empty() {
+        written.and(read)?;
+    }
+
+    let mut lines = reply.splitn(2, '\n');
+    let status = lines.next().unwrap_or("");
+    let output = lines.next().unwrap_or("").to_string();
+    match status {
+        "ok" => Ok(Ok(output)),
+        "error" => Ok(Err(output.trim_end().to_string())),
+        _ => Err(io::Error::new(io::ErrorKind::InvalidData, "unexpected reply from the control socket"))
+    }
+}
@@ -0,0 +1,123 @@
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{Shutdown, TcpStream};
+use std::time::Duration;
+
+/*
+ * Just enough HTTP/1.1 for the admin endpoint: one request per connection, no request body.
+ */
+
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+/* The most that is read of the request line and headers together */
+const MAX_REQUEST_SIZE: u64 = 8 * 1024;
+
+/* The most that is discarded of a request that was too large, before closing the connection */
+const MAX_DISCARD_SIZE: u64 = 64 * 1024;
+
+pub(crate) struct Request {
+    pub(crate) method: String,
+    /* Without the query string */
+    pub(crate) path: String
+}
+
+pub(crate) struct Response {
+    status: u16,
+    content_type: &'static str,
+    body: String
+}
+
+impl Response {
+    pub(crate) fn new(status: u16, content_type: &'static str, body: String) -> Self {
+        Response {
+            status,
+            content_type,
+            body
+        }
+    }
+}
+
+pub(crate) fn respond<F: FnOnce(&Request) -> Response>(stream: TcpStream, handler: F) -> io::Result<()> {
+    stream.set_read_timeout(Some(TIMEOUT))?;
+    stream.set_write_timeout(Some(TIMEOUT))?;
+
+    let mut reader = BufReader::new((&stream).take(MAX_REQUEST_SIZE));
+    let request = read_request(&mut reader)?;
+    let truncated = reader.get_ref().limit() == 0;
+    let response = match request {
+        Some(request) if !truncated => handler(&request),
+        // Malformed, or too large to be read completely
+        _ => Response::new(400, "text/plain", "Bad Request\n".to_string())
+    };
+
+    write_response(&stream, &response)?;
+
+    // Closing with unread input resets the connection, which may discard the response
+    if truncated {
+        discard(&stream)?;
+    }
+    Ok(())
+}
+
+/*
+ * Answer with `503` without reading the request, e.g. when too many connections are served
+ * already. Doesn't wait for the client, so the response may be lost.
+ */
+pub(crate) fn reject(stream: TcpStream) -> io::Result<()> {
+    stream.set_nonblocking(true)?;
+    let response = Response::new(503, "text/plain", "Service Unavailable\n".to_string());
+    write_response(&stream, &response)?;
+    discard(&stream)
+}
+
+fn write_response(mut stream: &TcpStream, response: &Response) -> io::Result<()> {
+    write!(
+        stream,
+        "HTTP/1.1 {} {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        response.status,
+        reason(response.status),
+        response.content_type,
+        response.body.len()
+    )?;
+    stream.write_all(response.body.as_bytes())?;
+    stream.flush()
+}
+
+fn discard(stream: &TcpStream) -> io::Result<()> {
+    stream.shutdown(Shutdown::Write)?;
+    io::copy(&mut stream.take(MAX_DISCARD_SIZE), &mut io::sink()).map(|_| ())
+}
+
+fn read_request<R: BufRead>(reader: &mut R) -> io::Result<Option<Request>> {
+    let mut line = String::new();
+    reader.read_line(&mut line)?;
+
+    let mut parts = line.split_whitespace();
+    let request = match (parts.next(), parts.next(), parts.next()) {
+        (Some(method), Some(target), Some(version)) if version.starts_with("HTTP/") => Request {
+            method: method.to_string(),
+            path: target.split('?').next().unwrap().to_string()
+        },
+        _ => return Ok(None)
+    };
+
+    // Headers are ignored, but have to be read before the client expects a response
+    loop {
+        line.clear();
+        if reader.read_line(&mut line)? == 0 || line.trim_end().is_empty() {
+            break;
+        }
+    }
+
+    Ok(Some(request))
+}
+
+fn reason(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        400 => "Bad Request",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        503 => "Service Unavailable",
+        _ => ""
+    }
+}
@@ -0,0 +1,188 @@
+//! # Sai Admin
+//!
+//! An admin HTTP endpoint for a [Sai](https://docs.rs/sai) System, so every service built on
+//! Sai can be inspected the same way. [AdminServer](struct.AdminServer.html) is a component
+//! like any other, and serves:
+//!
+//! - `GET /health`: `200`, or `503` if a component failed to start
+//! - `GET /ready`: `200` once every component has started, `503` before and while stopping
+//! - `GET /components`: the state of every component as JSON, see `System::status`
+//! - `GET /graph`: the dependency graph in the Graphviz DOT format
+//!
+//! ```
+//! use sai::{Component, System};
+//! use sai_admin::{AdminConfig, AdminServer};
+//!
+//! #[derive(Component)]
+//! struct Db {}
+//!
+//! # async fn run() {
+//! let mut system = System::builder()
+//!     .register::<Db>()
+//!     .register::<AdminServer>()
+//!     .instance(AdminConfig::new("127.0.0.1:9090"))
+//!     .build();
+//! system.start().await;
+//! # }
+//! ```
+//!
+//! The endpoint is served from threads of its own, one per connection, so it doesn't depend on
+//! an async runtime and keeps responding when the runtime is busy or a client is slow. Up to
+//! `AdminConfig::max_connections` are served at once, and further ones are answered with `503`.
+//!
+//! On Unix, [ControlServer](control/struct.ControlServer.html) serves a local control socket as
+//! well, through which the `sai-ctl` binary lists components, stops the system or restarts a
+//! component:
+//!
+//! ```text
+//! sai-ctl --socket /run/my-app/control.sock status
+//! sai-ctl --socket /run/my-app/control.sock restart DbPool
+//! sai-ctl --socket /run/my-app/control.sock stop
+//! ```
+
+use std::io;
+use std::net::{SocketAddr, TcpListener};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+use sai::{Component, ComponentLifecycle, Injected, SystemHandle, async_trait};
+use limit::ConnectionLimit;
+
+mod http;
+mod limit;
+mod render;
+
+#[cfg(unix)]
+pub mod control;
+#[cfg(unix)]
+pub use control::{ControlConfig, ControlServer};
+
+/* How often the accepting thread checks whether the server is stopping */
+const ACCEPT_INTERVAL: Duration = Duration::from_millis(50);
+
+/// How many connections are served at once by default
+pub const DEFAULT_MAX_CONNECTIONS: usize = 16;
+
+/// The configuration of [AdminServer](struct.AdminServer.html), which has to be registered with
+/// the System, e.g. via `SystemBuilder::instance` or a provider.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AdminConfig {
+    /// The address to listen on, e.g. `127.0.0.1:9090`. Port `0` picks a free port.
+    pub address: String,
+    /// How many connections are served at once
+    pub max_connections: usize
+}
+
+impl AdminConfig {
+    pub fn new<A: Into<String>>(address: A) -> Self {
+        AdminConfig {
+            address: address.into(),
+            max_connections: DEFAULT_MAX_CONNECTIONS
+        }
+    }
+}
+
+/// A component that serves the admin endpoint while the System is running
+#[derive(Component)]
+#[lifecycle]
+pub struct AdminServer {
+    #[injected]
+    config: Injected<AdminConfig>,
+
+    #[injected]
+    system: SystemHandle,
+
+    server: Option<Server>
+}
+
+impl AdminServer {
+    /// The address the endpoint listens on, once started
+    pub fn local_addr(&self) -> Option<SocketAddr> {
+        self.server.as_ref().map(|server| server.address)
+    }
+}
+
+#[async_trait]
+impl ComponentLifecycle for AdminServer {
+    async fn start(&mut self) {
+        let listener = TcpListener::bind(&self.config.address)
+            .and_then(|listener| listener.set_nonblocking(true).map(|_| listener))
+            .unwrap_or_else(|e| panic!("Unable to listen on {}: {}", self.config.address, e));
+        let address = listener.local_addr().unwrap();
+
+        let stopping = Arc::new(AtomicBool::new(false));
+        let system = self.system.clone();
+        let limit = ConnectionLimit::new(self.config.max_connections);
+        let thread = {
+            let stopping = stopping.clone();
+            thread::spawn(move || serve(listener, system, limit, stopping))
+        };
+
+        self.server = Some(Server {
+            address,
+            stopping,
+            thread
+        });
+    }
+
+    async fn stop(&mut self) {
+        if let Some(server) = self.server.take() {
+            // The accepting thread notices within ACCEPT_INTERVAL, requests in flight finish
+            // on their own threads
+            server.stopping.store(true, Ordering::SeqCst);
+            let _ = server.thread.join();
+        }
+    }
+
+    async fn stop_shared(&self) {
+        if let Some(server) = &self.server {
+            server.stopping.store(true, Ordering::SeqCst);
+        }
+    }
+}
+
+struct Server {
+    address: SocketAddr,
+    stopping: Arc<AtomicBool>,
+    thread: JoinHandle<()>
+}
+
+fn serve(listener: TcpListener, system: SystemHandle, limit: ConnectionLimit, stopping: Arc<AtomicBool>) {
+    while !stopping.load(Ordering::SeqCst) {
+        match listener.accept() {
+            // A slow or idle client only holds up its own thread
+            Ok((stream, _)) => match limit.acquire() {
+                Some(permit) => {
+                    let system = system.clone();
+                    thread::spawn(move || {
+                        let _ = stream
+                            .set_nonblocking(false)
+                            .and_then(|_| http::respond(stream, |request| route(request, &system)));
+                        drop(permit);
+                    });
+                },
+                None => {
+                    let _ = http::reject(stream);
+                }
+            },
+            Err(e) if e.kind() == io::ErrorKind::WouldBlock => thread::sleep(ACCEPT_INTERVAL),
+            // A broken connection only affects its own request, but don't spin on e.g. EMFILE
+            Err(_) => thread::sleep(ACCEPT_INTERVAL)
+        }
+    }
+}
+
+fn route(request: &http::Request, system: &SystemHandle) -> http::Response {
+    if request.method != "GET" {
+        return http::Response::new(405, "text/plain", "Method Not Allowed\n".to_string());
+    }
+
+    match request.path.as_str() {
+        "/health" => render::health(&system.status()),
+        "/ready" => render::ready(system.is_up()),
+        "/components" => render::components(&system.status()),
+        "/graph" => render::graph(&system.status()),
+        _ => http::Response::new(404, "text/plain", "Not Found\n".to_string())
+    }
+}
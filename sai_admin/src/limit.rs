@@ -0,0 +1,42 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/*
+ * Caps how many connections are served at once, as each of them has a thread of its own
+ */
+#[derive(Clone)]
+pub(crate) struct ConnectionLimit {
+    active: Arc<AtomicUsize>,
+    max: usize
+}
+
+/* Held while a connection is served */
+pub(crate) struct Permit {
+    active: Arc<AtomicUsize>
+}
+
+impl ConnectionLimit {
+    pub(crate) fn new(max: usize) -> Self {
+        ConnectionLimit {
+            active: Arc::new(AtomicUsize::new(0)),
+            max
+        }
+    }
+
+    /* None if `max` connections are being served already */
+    pub(crate) fn acquire(&self) -> Option<Permit> {
+        let max = self.max;
+        self.active
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |active| Some(active + 1).filter(|n| *n <= max))
+            .ok()
+            .map(|_| Permit {
+                active: self.active.clone()
+            })
+    }
+}
+
+impl Drop for Permit {
+    fn drop(&mut self) {
+        self.active.fetch_sub(1, Ordering::SeqCst);
+    }
+}
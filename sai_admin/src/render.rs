@@ -0,0 +1,95 @@
+use std::fmt::Write;
+use sai::{ComponentState, ComponentStatus};
+use super::http::Response;
+
+/*
+ * The bodies of the endpoints. The JSON is written by hand, as it's small and flat.
+ */
+
+pub(crate) fn health(status: &[ComponentStatus]) -> Response {
+    let failed: Vec<&str> = status
+        .iter()
+        .filter(|component| component.state == ComponentState::Failed)
+        .map(|component| component.name)
+        .collect();
+
+    if failed.is_empty() {
+        return Response::new(200, "application/json", "{\"status\":\"ok\"}".to_string());
+    }
+
+    let body = format!("{{\"status\":\"failed\",\"failed\":{}}}", strings(&failed));
+    Response::new(503, "application/json", body)
+}
+
+pub(crate) fn ready(up: bool) -> Response {
+    let status = if up { 200 } else { 503 };
+    Response::new(status, "application/json", format!("{{\"ready\":{}}}", up))
+}
+
+pub(crate) fn components(status: &[ComponentStatus]) -> Response {
+    let mut body = String::from("[");
+    for (i, component) in status.iter().enumerate() {
+        if i > 0 {
+            body.push(',');
+        }
+        let start_duration = match component.start_duration {
+            Some(duration) => format!("{:.3}", duration.as_secs_f64() * 1000.0),
+            None => "null".to_string()
+        };
+        write!(
+            body,
+            "{{\"name\":{},\"state\":\"{:?}\",\"start_duration_ms\":{},\"dependencies\":{},\"dependents\":{}}}",
+            string(component.name),
+            component.state,
+            start_duration,
+            strings(&component.dependencies),
+            strings(&component.dependents)
+        ).unwrap();
+    }
+    body.push(']');
+
+    Response::new(200, "application/json", body)
+}
+
+/// An edge points from a component to each of its dependencies
+pub(crate) fn graph(status: &[ComponentStatus]) -> Response {
+    let mut body = String::from("digraph system {\n");
+    for component in status {
+        writeln!(
+            body,
+            "    {} [label={}];",
+            string(component.name),
+            string(&format!("{}\n{:?}", component.name, component.state))
+        ).unwrap();
+    }
+    for component in status {
+        for dependency in &component.dependencies {
+            writeln!(body, "    {} -> {};", string(component.name), string(dependency)).unwrap();
+        }
+    }
+    body.push_str("}\n");
+
+    Response::new(200, "text/vnd.graphviz", body)
+}
+
+/* A quoted string, valid in both JSON and DOT */
+fn string(s: &str) -> String {
+    let mut quoted = String::with_capacity(s.len() + 2);
+    quoted.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            c if (c as u32) < 0x20 => write!(quoted, "\\u{:04x}", c as u32).unwrap(),
+            c => quoted.push(c)
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+fn strings(list: &[&str]) -> String {
+    let quoted: Vec<String> = list.iter().map(|s| string(s)).collect();
+    format!("[{}]", quoted.join(","))
+}
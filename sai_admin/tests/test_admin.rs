@@ -0,0 +1,127 @@
+use std::io::{Read, Write};
+use std::net::{SocketAddr, TcpStream};
+use sai::{Component, Injected, System};
+use sai_admin::{AdminConfig, AdminServer};
+
+#[derive(Component)]
+struct Db {}
+
+#[derive(Component)]
+#[allow(dead_code)]
+struct Http {
+    #[injected]
+    db: Injected<Db>
+}
+
+fn request(address: SocketAddr, method: &str, path: &str) -> (u16, String) {
+    let mut stream = TcpStream::connect(address).unwrap();
+    write!(stream, "{} {} HTTP/1.1\r\nHost: localhost\r\n\r\n", method, path).unwrap();
+
+    let mut response = String::new();
+    stream.read_to_string(&mut response).unwrap();
+
+    let status = response[9..12].parse().unwrap();
+    let body = response.split_once("\r\n\r\n").unwrap().1.to_string();
+    (status, body)
+}
+
+fn system() -> System<sai::Registry> {
+    System::builder()
+        .register::<Db>()
+        .register::<Http>()
+        .register::<AdminServer>()
+        .instance(AdminConfig::new("127.0.0.1:0"))
+        .build()
+}
+
+fn address(system: &System<sai::Registry>) -> SocketAddr {
+    system.get::<AdminServer>().unwrap().local_addr().unwrap()
+}
+
+#[tokio::test]
+async fn admin_endpoints() {
+    let mut system = system();
+    system.start().await;
+    let address = address(&system);
+
+    assert_eq!(request(address, "GET", "/health"), (200, "{\"status\":\"ok\"}".to_string()));
+    assert_eq!(request(address, "GET", "/ready"), (200, "{\"ready\":true}".to_string()));
+
+    let (status, components) = request(address, "GET", "/components");
+    assert_eq!(status, 200);
+    assert!(components.starts_with('[') && components.ends_with(']'));
+    assert!(components.contains(&format!(
+        "{{\"name\":\"{}\",\"state\":\"Started\",\"start_duration_ms\":",
+        std::any::type_name::<Http>()
+    )));
+    assert!(components.contains(&format!(
+        "\"dependencies\":[\"{}\"],\"dependents\":[]",
+        std::any::type_name::<Db>()
+    )));
+
+    let (status, graph) = request(address, "GET", "/graph");
+    assert_eq!(status, 200);
+    assert!(graph.starts_with("digraph system {\n"));
+    assert!(graph.contains(&format!(
+        "    \"{}\" -> \"{}\";\n",
+        std::any::type_name::<Http>(),
+        std::any::type_name::<Db>()
+    )));
+
+    assert_eq!(request(address, "GET", "/ready?verbose=1").0, 200);
+    assert_eq!(request(address, "GET", "/metrics").0, 404);
+    assert_eq!(request(address, "POST", "/health").0, 405);
+
+    system.stop().await.assert_no_leaks();
+    assert!(TcpStream::connect(address).is_err());
+}
+
+#[tokio::test]
+async fn admin_idle_connections_dont_block() {
+    let mut system = system();
+    system.start().await;
+    let address = address(&system);
+
+    // Connected, but never sends a request
+    let _idle = TcpStream::connect(address).unwrap();
+    assert_eq!(request(address, "GET", "/health").0, 200);
+
+    let mut stream = TcpStream::connect(address).unwrap();
+    write!(stream, "GET /health HTTP/1.1\r\nX-Padding: {}\r\n\r\n", "a".repeat(16 * 1024)).unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).unwrap();
+    assert!(response.starts_with("HTTP/1.1 400 "));
+
+    system.stop().await.assert_no_leaks();
+}
+
+#[tokio::test]
+async fn admin_connections_are_capped() {
+    let mut config = AdminConfig::new("127.0.0.1:0");
+    config.max_connections = 1;
+    let mut system = System::builder()
+        .register::<AdminServer>()
+        .instance(config)
+        .build();
+    system.start().await;
+    let address = address(&system);
+
+    let idle = TcpStream::connect(address).unwrap();
+    let mut response = String::new();
+    TcpStream::connect(address).unwrap().read_to_string(&mut response).unwrap();
+    assert!(response.starts_with("HTTP/1.1 503 "));
+
+    // Served again once the idle connection is gone
+    drop(idle);
+    let served = (0..100).any(|_| {
+        std::thread::sleep(std::time::Duration::from_millis(10));
+        let mut stream = TcpStream::connect(address).unwrap();
+        let mut response = String::new();
+        write!(stream, "GET /ready HTTP/1.1\r\n\r\n")
+            .and_then(|_| stream.read_to_string(&mut response))
+            .is_ok() && response.starts_with("HTTP/1.1 200 ")
+    });
+    assert!(served);
+
+    system.stop().await.assert_no_leaks();
+}
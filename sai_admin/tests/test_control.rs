@@ -0,0 +1,108 @@
+#![cfg(unix)]
+
+use std::io::Read;
+use std::os::unix::fs::PermissionsExt;
+use std::os::unix::net::UnixStream;
+use std::path::PathBuf;
+use sai::{Component, ComponentState, Injected, System};
+use sai_admin::{ControlConfig, ControlServer};
+use sai_admin::control::send;
+
+#[derive(Component)]
+struct Db {}
+
+#[derive(Component)]
+#[allow(dead_code)]
+struct Http {
+    #[injected]
+    db: Injected<Db>
+}
+
+fn socket(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("sai-{}-{}.sock", name, std::process::id()))
+}
+
+#[tokio::test]
+async fn control_socket() {
+    let path = socket("control");
+    let mut system = System::builder()
+        .register::<Db>()
+        .register::<Http>()
+        .register::<ControlServer>()
+        .instance(ControlConfig::new(path.clone()))
+        .build();
+    system.start().await;
+    assert_eq!(std::fs::metadata(&path).unwrap().permissions().mode() & 0o777, 0o600);
+
+    let operator = {
+        let path = path.clone();
+        std::thread::spawn(move || {
+            let status = send(&path, "status").unwrap().unwrap();
+            let restarted = send(&path, "restart Db").unwrap();
+            let unknown = send(&path, "restart Cache").unwrap();
+            let invalid = send(&path, "reboot").unwrap();
+            let stopping = send(&path, "stop").unwrap();
+            (status, restarted, unknown, invalid, stopping)
+        })
+    };
+    system.handle_requests().await;
+    let (status, restarted, unknown, invalid, stopping) = operator.join().unwrap();
+
+    assert!(status.lines().any(|line| {
+        line.starts_with("Started ") && line.ends_with(&format!("  {}", std::any::type_name::<Http>()))
+    }));
+    assert_eq!(restarted, Ok(format!(
+        "Restarted {}\nRestarted {}\n",
+        std::any::type_name::<Db>(),
+        std::any::type_name::<Http>()
+    )));
+    assert_eq!(unknown, Err("Cache is not a component of the system".to_string()));
+    assert_eq!(invalid, Err("Unknown command: reboot".to_string()));
+    assert_eq!(stopping, Ok("Stopping\n".to_string()));
+    assert!(system.status().iter().all(|c| c.state == ComponentState::Started));
+
+    system.stop().await.assert_no_leaks();
+    assert!(!path.exists());
+}
+
+#[tokio::test]
+async fn control_socket_limits() {
+    let path = socket("limits");
+    let mut config = ControlConfig::new(path.clone());
+    config.max_connections = 1;
+    let mut system = System::builder()
+        .register::<ControlServer>()
+        .instance(config)
+        .build();
+    system.start().await;
+    // Only the socket is left in its directory
+    let staged = std::fs::read_dir(path.parent().unwrap())
+        .unwrap()
+        .filter_map(|entry| entry.ok())
+        .any(|entry| entry.file_name().to_string_lossy().starts_with(&format!(".sai-limits-{}", std::process::id())));
+    assert!(!staged);
+
+    let idle = UnixStream::connect(&path).unwrap();
+    let mut rejected = String::new();
+    UnixStream::connect(&path).unwrap().read_to_string(&mut rejected).unwrap();
+    assert_eq!(rejected, "error\nToo many connections\n");
+
+    // Connections are served again once the idle one is gone
+    drop(idle);
+    let retry = |command: &str| {
+        for _ in 0..100 {
+            match send(&path, command) {
+                // Turned away, possibly before the command was written
+                Ok(Err(message)) if message == "Too many connections" => {},
+                Err(_) => {},
+                Ok(reply) => return reply
+            }
+            std::thread::sleep(std::time::Duration::from_millis(10));
+        }
+        panic!("{} was never served", command);
+    };
+    assert!(retry("status").is_ok());
+    assert_eq!(retry(&"x".repeat(4096)), Err("The command is too long".to_string()));
+
+    system.stop().await.assert_no_leaks();
+}
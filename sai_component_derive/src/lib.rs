@@ -18,6 +18,7 @@ use proc_macro::TokenStream;
 #[macro_use]
 extern crate syn;
 use syn::{DeriveInput, Data, Field};
+use syn::spanned::Spanned;
 use syn::Meta::{Path};
 use proc_macro2::TokenStream as TokenStream2;
 #[macro_use]
@@ -26,48 +27,136 @@ extern crate quote;
 mod symbol;
 use symbol::*;
 
+mod provider;
+
 
 /// Dervive Macro for defining a component
-#[proc_macro_derive(Component, attributes(injected, lifecycle))]
+#[proc_macro_derive(Component, attributes(injected, lifecycle, component))]
 pub fn derive_component(input: TokenStream) -> TokenStream {
     let ast = parse_macro_input!(input as DeriveInput);
-    // println!("{}", ast.attrs);
+
+    match expand_component(&ast) {
+        Ok(tokens) => tokens.into(),
+        Err(e) => e.to_compile_error().into()
+    }
+}
+
+/// Attribute Macro for registering the value returned by an async function as a component
+///
+/// ```ignore
+/// #[sai::provider(teardown = "close_pool")]
+/// async fn pg_pool(config: Injected<DbConfig>) -> Result<Pool, Error> {
+///     Pool::connect(&config.url).await
+/// }
+///
+/// async fn close_pool(pool: &Pool) {
+///     pool.close().await
+/// }
+///
+/// component_registry!(RootRegistry, [DbConfig, pg_pool]);
+/// ```
+///
+/// Please refer the doc of Sai to understand details.
+#[proc_macro_attribute]
+pub fn provider(args: TokenStream, input: TokenStream) -> TokenStream {
+    let args = parse_macro_input!(args as syn::AttributeArgs);
+    let item = parse_macro_input!(input as syn::ItemFn);
+
+    match provider::expand_provider(args, item) {
+        Ok(tokens) => tokens.into(),
+        Err(e) => e.to_compile_error().into()
+    }
+}
+
+fn expand_component(ast: &DeriveInput) -> syn::Result<TokenStream2> {
     let ident = &ast.ident;
 
-    let fields = parse_struct_data(&ast.data);
+    let fields = parse_struct_data(ast)?;
+
+    let attrs = parse_component_attributes(ast)?;
+    let scope_tokens = build_scope(&attrs);
+    let profiles = &attrs.profiles;
+    let enabled_if = build_enabled_if(&attrs.enabled_if);
+
+    let (build_tokens, mut depends_on_tokens, mut requires_tokens) = match &attrs.constructor {
+        Some(constructor) => {
+            check_no_injected_fields(&fields)?;
+            // Errors about the signature of the constructor point at the constructor
+            let span = constructor.span();
+            (
+                quote_spanned! {span=> sai::Constructor::<_, Self>::construct(&#constructor, registry) },
+                quote_spanned! {span=> sai::Constructor::<_, Self>::depends_on(&#constructor) },
+                quote_spanned! {span=> sai::Constructor::<_, Self>::requires(&#constructor) }
+            )
+        },
+        None => {
+            let fields_tokens = build_struct_fields(&fields);
+            (
+                quote! { #ident { #fields_tokens } },
+                build_depends_on(&fields),
+                build_requires(&fields)
+            )
+        }
+    };
 
-    let fields_tokens = build_struct_fields(&fields);
+    // Ordering-only dependencies from #[component(after(...))]
+    if !attrs.after.is_empty() {
+        let after = &attrs.after;
+        depends_on_tokens = quote! {{
+            let mut depends_on: std::vec::Vec<std::any::TypeId> = #depends_on_tokens;
+            #(depends_on.push(<#after as sai::Registrable>::component_type_id());)*
+            depends_on
+        }};
+        requires_tokens = quote! {{
+            let mut requires: std::vec::Vec<(std::any::TypeId, &'static str)> = #requires_tokens;
+            #(requires.push((
+                <#after as sai::Registrable>::component_type_id(),
+                std::any::type_name::<#after>()
+            ));)*
+            requires
+        }};
+    }
 
-    let depends_on_tokens = build_depends_on(&fields);
+    let impl_lifecycle_tokens = build_impl_component_lifecycle(ast)?;
+    let impl_ordered_tokens = build_impl_ordered(ast, &attrs, &fields)?;
+    let impl_wire_tokens = build_impl_wire(ast, &attrs, &fields)?;
 
-    let impl_lifecycle_tokens = build_impl_component_lifecycle(&ast);
+    let generics = build_generics(&ast.generics)?;
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
 
     let tokens = quote!{
-        impl sai::Component for #ident {
-            fn build(registry: &sai::ComponentRepository) -> #ident {
-                return #ident {
-                    #fields_tokens
-                }
+        impl #impl_generics sai::Component for #ident #ty_generics #where_clause {
+            fn build(registry: &sai::ComponentRepository) -> Self {
+                return #build_tokens
 
             }
 
             // Used during topology sort to calculate DAG
-            fn meta() -> sai::ComponentMeta<std::boxed::Box<#ident>> {
+            fn meta() -> sai::ComponentMeta<std::boxed::Box<Self>> {
 
                 return sai::ComponentMeta {
-                    type_id: std::any::TypeId::of::<sai::Injected<#ident>>(),
+                    type_id: std::any::TypeId::of::<sai::Injected<Self>>(),
+                    name: std::any::type_name::<Self>(),
                     depends_on: #depends_on_tokens,
+                    requires: #requires_tokens,
                     build: std::boxed::Box::new(
-                        |repo: &sai::ComponentRepository| std::boxed::Box::new(#ident::build(repo))
-                    )
+                        |repo: &sai::ComponentRepository| std::boxed::Box::new(<Self as sai::Component>::build(repo))
+                    ),
+                    scope: #scope_tokens,
+                    profiles: &[#(#profiles),*],
+                    enabled_if: #enabled_if
                 }
             }
         }
 
         #impl_lifecycle_tokens
+
+        #impl_ordered_tokens
+
+        #impl_wire_tokens
     };
 
-    return tokens.into();
+    Ok(tokens)
 }
 
 fn build_struct_fields (fields: &Vec<ComponentField>) -> TokenStream2 {
@@ -78,15 +167,8 @@ fn build_struct_fields (fields: &Vec<ComponentField>) -> TokenStream2 {
             let ident = &f.ident;
             let ty = &f.ty; // expecting Injected<Bluh>
             if f.injected {
-                let error_msg_type_not_found = format!("Unable to find type {} in component repository", quote!{#ty});
-                let error_msg_cast_failure = format!("Found {} in component repository. But unable to downcast it", quote!{#ty});
-
                 return quote! {
-                    #ident: {
-                        let comp: &sai::Injected::<dyn sai::Component> = registry.get_by_typeid(std::any::TypeId::of::<#ty>()).expect(#error_msg_type_not_found);
-                        let dep: #ty = comp.clone().downcast().expect(#error_msg_cast_failure);
-                        dep
-                    }
+                    #ident: <#ty as sai::Dependency>::resolve(registry)
                 }
             } else {
                 return quote! {
@@ -106,99 +188,496 @@ fn build_depends_on(fields: &Vec<ComponentField>) -> TokenStream2 {
 
     let x: Vec<TokenStream2> = fields
         .into_iter()
-        .filter(|f| f.injected)
+        .filter(|f| f.injected && !f.deferred)
         .map(|f| {
             let ty = &f.ty;
             return quote! {
-                std::any::TypeId::of::<#ty>()
+                <#ty as sai::Dependency>::depends_on()
             }
         })
         .collect();
 
-    quote! {
-        vec![ #(#x),* ]
+    quote! {{
+        let depends_on: std::vec::Vec<std::option::Option<std::any::TypeId>> = vec![ #(#x),* ];
+        depends_on.into_iter().flatten().collect()
+    }}
+}
+
+fn build_requires(fields: &[ComponentField]) -> TokenStream2 {
+
+    let x: Vec<TokenStream2> = fields
+        .iter()
+        .filter(|f| f.injected)
+        .map(|f| {
+            let ty = &f.ty;
+            quote! {
+                <#ty as sai::Dependency>::requires()
+            }
+        })
+        .collect();
+
+    quote! {{
+        let requires: std::vec::Vec<std::option::Option<(std::any::TypeId, &'static str)>> = vec![ #(#x),* ];
+        requires.into_iter().flatten().collect()
+    }}
+}
+
+/// The depth of the component in a registry defined by `component_registry!(..., ordered)`.
+/// The parameters of a constructor are unknown here, so such components can't be ordered.
+fn build_impl_ordered(
+    ast: &DeriveInput,
+    attrs: &ComponentAttributes,
+    fields: &[ComponentField]
+) -> syn::Result<TokenStream2> {
+    if attrs.constructor.is_some() {
+        return Ok(quote! {})
+    }
+
+    let ident = &ast.ident;
+    let (_, ty_generics, _) = ast.generics.split_for_impl();
+
+    let mut dependencies: Vec<TokenStream2> = fields
+        .iter()
+        .filter(|f| f.injected)
+        .map(|f| {
+            let ty = &f.ty;
+            quote! { #ty }
+        })
+        .collect();
+    for after in attrs.after.iter() {
+        dependencies.push(quote! { <#after as sai::Registrable>::Binding });
+    }
+
+    let mut generics = build_generics(&ast.generics)?;
+    generics.params.push(parse_quote! { __SaiRegistry });
+    let predicates: Vec<syn::WherePredicate> = dependencies
+        .iter()
+        .map(|ty| parse_quote! { #ty: sai::OrderedDependency<__SaiRegistry> })
+        .collect();
+    generics.make_where_clause().predicates.extend(predicates);
+    let (impl_generics, _, where_clause) = generics.split_for_impl();
+
+    Ok(quote! {
+        impl #impl_generics sai::Ordered<__SaiRegistry> for #ident #ty_generics #where_clause {
+            const DEPTH: usize = sai::depth(&[
+                #(<#dependencies as sai::OrderedDependency<__SaiRegistry>>::DEPTH),*
+            ]);
+        }
+    })
+}
+
+/// Building the component in a system generated by `sai::system!`, from the components started
+/// before it. The position of each dependency, including `after(...)` ones, is a type parameter
+/// inferred by the compiler.
+fn build_impl_wire(
+    ast: &DeriveInput,
+    attrs: &ComponentAttributes,
+    fields: &[ComponentField]
+) -> syn::Result<TokenStream2> {
+    if attrs.constructor.is_some() {
+        return Ok(quote! {})
+    }
+
+    let ident = &ast.ident;
+    let (_, ty_generics, _) = ast.generics.split_for_impl();
+
+    let mut generics = build_generics(&ast.generics)?;
+    generics.params.push(parse_quote! { __SaiContext });
+
+    let mut indices: Vec<syn::Ident> = Vec::new();
+    let mut predicates: Vec<syn::WherePredicate> = Vec::new();
+    let mut field_tokens: Vec<TokenStream2> = Vec::new();
+    for f in fields.iter() {
+        let field_ident = &f.ident;
+        let ty = &f.ty;
+        if f.injected {
+            let index = format_ident!("__SaiIndex{}", indices.len());
+            generics.params.push(parse_quote! { #index });
+            predicates.push(parse_quote! { #ty: sai::WiredDependency<__SaiContext, #index> });
+            field_tokens.push(quote! {
+                #field_ident: <#ty as sai::WiredDependency<__SaiContext, #index>>::wire(context)
+            });
+            indices.push(index);
+        } else {
+            field_tokens.push(quote! { #field_ident: Default::default() });
+        }
+    }
+    // Ordering-only dependencies aren't wired, but have to be started before
+    for after in attrs.after.iter() {
+        let index = format_ident!("__SaiIndex{}", indices.len());
+        generics.params.push(parse_quote! { #index });
+        predicates.push(parse_quote! {
+            <#after as sai::Registrable>::Binding: sai::WiredDependency<__SaiContext, #index>
+        });
+        indices.push(index);
     }
+    generics.make_where_clause().predicates.extend(predicates);
+    let (impl_generics, _, where_clause) = generics.split_for_impl();
+
+    Ok(quote! {
+        impl #impl_generics sai::Wire<__SaiContext, (#(#indices,)*)> for #ident #ty_generics #where_clause {
+            fn wire(context: &__SaiContext) -> Self {
+                #ident { #(#field_tokens),* }
+            }
+        }
+    })
 }
 
-fn build_impl_component_lifecycle(ast: &DeriveInput) -> TokenStream2 {
+fn build_impl_component_lifecycle(ast: &DeriveInput) -> syn::Result<TokenStream2> {
     let ident = &ast.ident;
+    let generics = build_generics(&ast.generics)?;
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    if is_lifecycle_mode(ast)? {
+        Ok(quote! {})
+    } else {
+        Ok(quote! {
+            impl #impl_generics sai::ComponentLifecycle for #ident #ty_generics #where_clause {}
+        })
+    }
+}
+
+/// A component is shared between threads and identified by its TypeId,
+/// so every type parameter has to be `Send + Sync + 'static`.
+fn build_generics(generics: &syn::Generics) -> syn::Result<syn::Generics> {
+    if let Some(lifetime) = generics.lifetimes().next() {
+        return Err(syn::Error::new_spanned(lifetime, "Component can not have lifetime parameters"));
+    }
+
+    let mut generics = generics.clone();
+    let bounds: Vec<syn::WherePredicate> = generics
+        .type_params()
+        .map(|param| {
+            let ident = &param.ident;
+            parse_quote! { #ident: std::marker::Send + std::marker::Sync + 'static }
+        })
+        .collect();
+    generics.make_where_clause().predicates.extend(bounds);
+
+    Ok(generics)
+}
+
+fn is_lifecycle_mode(ast: &DeriveInput) -> syn::Result<bool> {
+    let mut result = false;
+    for attr in ast.attrs.iter().filter(|attr| attr.path == LIFECYCLE) {
+        match attr.parse_meta()? {
+            Path(_) => result = true,
+            meta => return Err(syn::Error::new_spanned(meta, "Expecting #[lifecycle] without arguments"))
+        }
+    }
+    Ok(result)
+}
 
-    if is_lifecycle_mode(ast) {
-        return quote! {}
+
+fn build_scope(attrs: &ComponentAttributes) -> TokenStream2 {
+    if attrs.transient {
+        quote! { sai::ComponentScope::Transient }
+    } else if attrs.lazy {
+        quote! { sai::ComponentScope::Lazy }
     } else {
-        return quote! {
-            impl sai::ComponentLifecycle for #ident {}
+        quote! { sai::ComponentScope::Singleton }
+    }
+}
+
+/// Options specified by `#[component(...)]` on the struct
+#[derive(Default)]
+struct ComponentAttributes {
+    transient: bool,
+    lazy: bool,
+    constructor: Option<syn::ExprPath>,
+    /* Components to start before this one, without injecting them */
+    after: Vec<syn::Path>,
+    profiles: Vec<syn::LitStr>,
+    enabled_if: Option<syn::LitStr>
+}
+
+fn parse_component_attributes(ast: &DeriveInput) -> syn::Result<ComponentAttributes> {
+    let mut result = ComponentAttributes::default();
+    let mut errors = Errors::default();
+    let mut lazy = None;
+
+    for attr in ast.attrs.iter().filter(|attr| attr.path == COMPONENT) {
+        let list = match attr.parse_meta()? {
+            syn::Meta::List(list) => list,
+            meta => {
+                errors.push(syn::Error::new_spanned(meta, "Expecting #[component(...)]"));
+                continue;
+            }
+        };
+
+        for nested in list.nested.iter() {
+            match nested {
+                syn::NestedMeta::Meta(syn::Meta::NameValue(nv)) if nv.path == SCOPE => {
+                    match &nv.lit {
+                        syn::Lit::Str(s) if s.value() == "transient" => result.transient = true,
+                        syn::Lit::Str(s) if s.value() == "singleton" => result.transient = false,
+                        lit => errors.push(syn::Error::new_spanned(
+                            lit,
+                            "scope can only be \"singleton\" or \"transient\""
+                        ))
+                    }
+                },
+                syn::NestedMeta::Meta(syn::Meta::NameValue(nv)) if nv.path == CONSTRUCTOR => {
+                    match &nv.lit {
+                        syn::Lit::Str(s) => match s.parse::<syn::ExprPath>() {
+                            Ok(path) => result.constructor = Some(path),
+                            Err(_) => errors.push(syn::Error::new_spanned(
+                                s,
+                                "constructor has to be a path to a function, e.g. \"Foo::new\""
+                            ))
+                        },
+                        lit => errors.push(syn::Error::new_spanned(
+                            lit,
+                            "constructor has to be a path to a function, e.g. \"Foo::new\""
+                        ))
+                    }
+                },
+                syn::NestedMeta::Meta(syn::Meta::NameValue(nv)) if nv.path == PROFILE => {
+                    match parse_profile(&nv.lit) {
+                        Ok(profile) => result.profiles.push(profile),
+                        Err(e) => errors.push(e)
+                    }
+                },
+                syn::NestedMeta::Meta(syn::Meta::NameValue(nv)) if nv.path == ENABLED_IF => {
+                    match parse_enabled_if(&nv.lit) {
+                        Ok(key) => result.enabled_if = Some(key),
+                        Err(e) => errors.push(e)
+                    }
+                },
+                syn::NestedMeta::Meta(syn::Meta::Path(p)) if p == LAZY => {
+                    result.lazy = true;
+                    lazy = Some(p.clone());
+                },
+                syn::NestedMeta::Meta(syn::Meta::List(l)) if l.path == AFTER => {
+                    for item in l.nested.iter() {
+                        match item {
+                            syn::NestedMeta::Meta(syn::Meta::Path(path)) => result.after.push(path.clone()),
+                            item => errors.push(syn::Error::new_spanned(
+                                item,
+                                "after(...) expects components, e.g. after(Migrations)"
+                            ))
+                        }
+                    }
+                },
+                _ => errors.push(syn::Error::new_spanned(nested, "Unknown option in #[component(...)]"))
+            }
         }
     }
+
+    if let (true, Some(path)) = (result.transient, lazy) {
+        errors.push(syn::Error::new_spanned(
+            path,
+            "A transient component is created for each dependent, so it can't be lazy"
+        ));
+    }
+
+    errors.finish(result)
 }
 
-fn is_lifecycle_mode(ast: &DeriveInput) -> bool {
-    return ast
-        .attrs
-        .iter()
-        .map(|attr| attr.path == LIFECYCLE)
-        .any(|i| i);
+/// `profile = "prod"`, which can be repeated for multiple profiles
+fn parse_profile(lit: &syn::Lit) -> syn::Result<syn::LitStr> {
+    match lit {
+        syn::Lit::Str(s) if !s.value().is_empty() => Ok(s.clone()),
+        lit => Err(syn::Error::new_spanned(
+            lit,
+            "profile has to be the name of a profile, e.g. \"prod\""
+        ))
+    }
+}
+
+/// `enabled_if = "features.search"`
+fn parse_enabled_if(lit: &syn::Lit) -> syn::Result<syn::LitStr> {
+    match lit {
+        syn::Lit::Str(s) if !s.value().is_empty() => Ok(s.clone()),
+        lit => Err(syn::Error::new_spanned(
+            lit,
+            "enabled_if has to be a configuration key, e.g. \"features.search\""
+        ))
+    }
 }
 
+fn build_enabled_if(key: &Option<syn::LitStr>) -> TokenStream2 {
+    match key {
+        Some(key) => quote! { std::option::Option::Some(#key) },
+        None => quote! { std::option::Option::None }
+    }
+}
+
+/// Collects errors so that all of them are reported at once
+#[derive(Default)]
+struct Errors {
+    error: Option<syn::Error>
+}
+
+impl Errors {
+    fn push(&mut self, e: syn::Error) {
+        match &mut self.error {
+            Some(error) => error.combine(e),
+            None => self.error = Some(e)
+        }
+    }
+
+    fn finish<T>(self, value: T) -> syn::Result<T> {
+        match self.error {
+            Some(e) => Err(e),
+            None => Ok(value)
+        }
+    }
+}
 
 struct ComponentField {
     injected: bool,
+    /* #[injected(deferred)], resolved without an ordering edge */
+    deferred: bool,
     ident: syn::Ident,
     ty: syn::Type
 }
 
-fn parse_struct_data (data: &Data) -> Vec<ComponentField> {
+fn parse_struct_data (ast: &DeriveInput) -> syn::Result<Vec<ComponentField>> {
 
-    let s = match data {
+    let s = match &ast.data {
         Data::Struct(s) => s,
-        _ => panic!("Component macro can only be used on struct enum")
+        Data::Enum(e) => return Err(syn::Error::new_spanned(
+            e.enum_token,
+            "Component macro can only be used on struct, not enum"
+        )),
+        Data::Union(u) => return Err(syn::Error::new_spanned(
+            u.union_token,
+            "Component macro can only be used on struct, not union"
+        ))
     };
 
 
     let fields = match &s.fields {
         syn::Fields::Named(f) => f,
-        syn::Fields::Unit => return Vec::new(),
-        _ => panic!("Component marco can not be used on tuple struct")
+        syn::Fields::Unit => return Ok(Vec::new()),
+        syn::Fields::Unnamed(f) => return Err(syn::Error::new_spanned(
+            f,
+            "Component macro can not be used on tuple struct"
+        ))
     };
     let fields = &fields.named;
 
-    return fields
-        .iter()
-        .map(parse_struct_field)
-        .collect::<Vec<ComponentField>>();
+    let mut errors = Errors::default();
+    let mut result = Vec::new();
+    for field in fields.iter() {
+        match parse_struct_field(field) {
+            Ok(f) => result.push(f),
+            Err(e) => errors.push(e)
+        }
+    }
+
+    errors.finish(result)
 }
 
-fn parse_struct_field (field: &Field) -> ComponentField {
+fn parse_struct_field (field: &Field) -> syn::Result<ComponentField> {
 
     let ty = field.ty.clone();
     let ident = field.ident.clone().unwrap();
     let attrs = &field.attrs;
 
-    let injected = attrs
-        .iter()
-        .map(|attr| is_injected_attribute(attr))
-        .any(|i| match i {
-            Ok(v) => v,
-            Err(_) => false // TODO: improve error handling
-        });
+    let mut injection = None;
+    for attr in attrs.iter() {
+        if let Some(i) = parse_injected_attribute(attr)? {
+            injection = Some(i);
+        }
+    }
 
+    match injection {
+        Some(Injection::Direct) => check_injected_type(&ty)?,
+        Some(Injection::Deferred) => check_deferred_type(&ty)?,
+        None => {}
+    }
 
-    return ComponentField {
-        injected,
+    Ok(ComponentField {
+        injected: injection.is_some(),
+        deferred: injection == Some(Injection::Deferred),
         ident,
         ty
-    }
+    })
 }
 
-fn is_injected_attribute(attr: &syn::Attribute) -> Result<bool, ()> {
+#[derive(Clone, Copy, PartialEq)]
+enum Injection {
+    Direct,
+    Deferred
+}
+
+fn parse_injected_attribute(attr: &syn::Attribute) -> syn::Result<Option<Injection>> {
     if attr.path != INJECTED {
-        return Ok(false)
+        return Ok(None)
+    }
+
+    match attr.parse_meta()? {
+        Path(_) => Ok(Some(Injection::Direct)),
+        syn::Meta::List(list) => match list.nested.iter().collect::<Vec<_>>().as_slice() {
+            [syn::NestedMeta::Meta(Path(p))] if p == DEFERRED => Ok(Some(Injection::Deferred)),
+            _ => Err(syn::Error::new_spanned(
+                list.nested,
+                "Unexpected argument in #[injected], only #[injected(deferred)] is supported"
+            ))
+        },
+        meta => Err(syn::Error::new_spanned(meta, "Expecting #[injected]"))
     }
+}
 
-    match attr.parse_meta() {
-        Ok(Path(_)) => Ok(true), // Only expect #[injected]
-        Ok(_) => Err(()), // TODO: improve error handling
-        Err(_) => Err(()) // TODO: improve error handling
+/// With a constructor, all dependencies come from the constructor's parameters
+fn check_no_injected_fields(fields: &[ComponentField]) -> syn::Result<()> {
+    let mut errors = Errors::default();
+    for f in fields.iter().filter(|f| f.injected) {
+        errors.push(syn::Error::new_spanned(
+            &f.ident,
+            "#[injected] can not be used together with #[component(constructor = \"...\")], \
+             take the dependency as a parameter of the constructor instead"
+        ));
+    }
+    errors.finish(())
+}
+
+/// Types that can be used for an `#[injected]` field
+const INJECTED_WRAPPERS: &[Symbol] = &[INJECTED_TYPE, LAZY_TYPE];
+
+fn check_injected_type(ty: &syn::Type) -> syn::Result<()> {
+    let segment = match ty {
+        syn::Type::Path(p) if p.qself.is_none() => p.path.segments.last(),
+        _ => None
+    };
+
+    let supported = segment
+        .map(|s| match s.arguments {
+            syn::PathArguments::AngleBracketed(_) => INJECTED_WRAPPERS.iter().any(|w| s.ident == *w),
+            syn::PathArguments::None => s.ident == SYSTEM_HANDLE_TYPE,
+            _ => false
+        })
+        .unwrap_or(false);
+
+    if supported {
+        Ok(())
+    } else {
+        Err(syn::Error::new_spanned(
+            ty,
+            "#[injected] field must be of type `Injected<...>`, `Lazy<Injected<...>>`, `SystemHandle`, \
+             or `Deferred<Injected<...>>` with #[injected(deferred)]"
+        ))
+    }
+}
+
+fn check_deferred_type(ty: &syn::Type) -> syn::Result<()> {
+    let supported = match ty {
+        syn::Type::Path(p) if p.qself.is_none() => p.path.segments
+            .last()
+            .map(|s| s.ident == DEFERRED_TYPE && matches!(s.arguments, syn::PathArguments::AngleBracketed(_)))
+            .unwrap_or(false),
+        _ => false
+    };
+
+    if supported {
+        Ok(())
+    } else {
+        Err(syn::Error::new_spanned(
+            ty,
+            "#[injected(deferred)] field must be of type `Deferred<Injected<...>>`"
+        ))
     }
 }
 
@@ -210,4 +689,3 @@ mod tests {
         assert_eq!(2 + 2, 4);
     }
 }
-
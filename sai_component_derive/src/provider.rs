@@ -0,0 +1,217 @@
+use proc_macro2::TokenStream as TokenStream2;
+use syn::spanned::Spanned;
+use syn::{AttributeArgs, ItemFn, Lit, Meta, NestedMeta, ReturnType};
+
+use super::{Errors, build_enabled_if, parse_enabled_if, parse_profile};
+use super::symbol::*;
+
+pub fn expand_provider(args: AttributeArgs, item: ItemFn) -> syn::Result<TokenStream2> {
+    let ProviderArguments { teardown, profiles, enabled_if } = parse_provider_arguments(&args)?;
+    let enabled_if = build_enabled_if(&enabled_if);
+
+    let sig = &item.sig;
+    let fn_ident = &sig.ident;
+    let vis = &item.vis;
+
+    if sig.asyncness.is_none() {
+        return Err(syn::Error::new_spanned(sig.fn_token, "A provider has to be an async fn"));
+    }
+    if !sig.generics.params.is_empty() {
+        return Err(syn::Error::new_spanned(&sig.generics, "A provider can not be generic"));
+    }
+
+    let (provided_ty, fallible) = parse_return_type(sig)?;
+    let result_check = if fallible {
+        build_result_check(sig, &provided_ty)
+    } else {
+        quote! {}
+    };
+
+    let mut errors = Errors::default();
+    let mut arg_types = Vec::new();
+    for input in sig.inputs.iter() {
+        match input {
+            syn::FnArg::Typed(t) => arg_types.push(&*t.ty),
+            syn::FnArg::Receiver(r) => errors.push(syn::Error::new_spanned(
+                r,
+                "A provider can not take self"
+            ))
+        }
+    }
+    errors.finish(())?;
+
+    let arg_idents: Vec<syn::Ident> = (0..arg_types.len())
+        .map(|i| format_ident!("__arg{}", i))
+        .collect();
+
+    let call = if fallible {
+        quote! {
+            sai::ProviderResult::into_result(#fn_ident(#(#arg_idents),*).await)
+                .map_err(|e| format!("{:?}", e))
+        }
+    } else {
+        quote! { std::result::Result::<_, std::string::String>::Ok(#fn_ident(#(#arg_idents),*).await) }
+    };
+
+    let teardown_tokens = match &teardown {
+        Some(path) => quote! {
+            std::option::Option::Some({
+                fn teardown<'a>(value: &'a #provided_ty)
+                    -> std::pin::Pin<std::boxed::Box<dyn std::future::Future<Output = ()> + std::marker::Send + 'a>>
+                {
+                    std::boxed::Box::pin(#path(value))
+                }
+                teardown
+            })
+        },
+        None => quote! { std::option::Option::None }
+    };
+
+    let name = format!("::{}", fn_ident);
+
+    let tokens = quote! {
+        #item
+
+        #result_check
+
+        /// The component registered by the provider function with the same name
+        #[allow(non_camel_case_types)]
+        #vis struct #fn_ident {}
+
+        impl sai::Registrable for #fn_ident {
+            type Binding = sai::Injected<#provided_ty>;
+
+            fn component_type_id() -> std::any::TypeId {
+                std::any::TypeId::of::<sai::Injected<#provided_ty>>()
+            }
+
+            fn component_meta() -> sai::ComponentMeta<std::boxed::Box<dyn sai::Component>> {
+                return sai::ComponentMeta {
+                    type_id: <Self as sai::Registrable>::component_type_id(),
+                    name: concat!(module_path!(), #name),
+                    depends_on: {
+                        let depends_on: std::vec::Vec<std::option::Option<std::any::TypeId>> =
+                            vec![ #(<#arg_types as sai::Dependency>::depends_on()),* ];
+                        depends_on.into_iter().flatten().collect()
+                    },
+                    requires: {
+                        let requires: std::vec::Vec<std::option::Option<(std::any::TypeId, &'static str)>> =
+                            vec![ #(<#arg_types as sai::Dependency>::requires()),* ];
+                        requires.into_iter().flatten().collect()
+                    },
+                    build: std::boxed::Box::new(|registry: &sai::ComponentRepository| {
+                        #(let #arg_idents = <#arg_types as sai::Dependency>::resolve(registry);)*
+                        sai::provided::<#provided_ty, _>(
+                            concat!(module_path!(), #name),
+                            move || std::boxed::Box::pin(async move { #call }),
+                            #teardown_tokens
+                        )
+                    }),
+                    scope: sai::ComponentScope::Singleton,
+                    profiles: &[#(#profiles),*],
+                    enabled_if: #enabled_if
+                }
+            }
+        }
+
+        impl<__SaiRegistry> sai::Ordered<__SaiRegistry> for #fn_ident
+            where #(#arg_types: sai::OrderedDependency<__SaiRegistry>),*
+        {
+            const DEPTH: usize = sai::depth(&[
+                #(<#arg_types as sai::OrderedDependency<__SaiRegistry>>::DEPTH),*
+            ]);
+        }
+    };
+
+    Ok(tokens)
+}
+
+struct ProviderArguments {
+    teardown: Option<syn::ExprPath>,
+    profiles: Vec<syn::LitStr>,
+    enabled_if: Option<syn::LitStr>
+}
+
+/// `#[sai::provider]` or e.g. `#[sai::provider(teardown = "path::to::fn", profile = "prod")]`
+fn parse_provider_arguments(args: &AttributeArgs) -> syn::Result<ProviderArguments> {
+    let mut teardown = None;
+    let mut profiles = Vec::new();
+    let mut enabled_if = None;
+    let mut errors = Errors::default();
+
+    for arg in args.iter() {
+        match arg {
+            NestedMeta::Meta(Meta::NameValue(nv)) if nv.path == ENABLED_IF => {
+                match parse_enabled_if(&nv.lit) {
+                    Ok(key) => enabled_if = Some(key),
+                    Err(e) => errors.push(e)
+                }
+            },
+            NestedMeta::Meta(Meta::NameValue(nv)) if nv.path == PROFILE => {
+                match parse_profile(&nv.lit) {
+                    Ok(profile) => profiles.push(profile),
+                    Err(e) => errors.push(e)
+                }
+            },
+            NestedMeta::Meta(Meta::NameValue(nv)) if nv.path == TEARDOWN => {
+                match &nv.lit {
+                    Lit::Str(s) => match s.parse::<syn::ExprPath>() {
+                        Ok(path) => teardown = Some(path),
+                        Err(_) => errors.push(syn::Error::new_spanned(
+                            s,
+                            "teardown has to be a path to an async function, e.g. \"close_pool\""
+                        ))
+                    },
+                    lit => errors.push(syn::Error::new_spanned(
+                        lit,
+                        "teardown has to be a path to an async function, e.g. \"close_pool\""
+                    ))
+                }
+            },
+            _ => errors.push(syn::Error::new_spanned(arg, "Unknown option in #[provider(...)]"))
+        }
+    }
+
+    errors.finish(ProviderArguments { teardown, profiles, enabled_if })
+}
+
+/// The provided type, and whether the provider returns a `Result`.
+/// A type named `Result` may well be an alias, so `build_result_check` makes sure that the
+/// first type argument is what it returns on success.
+fn parse_return_type(sig: &syn::Signature) -> syn::Result<(syn::Type, bool)> {
+    let ty = match &sig.output {
+        ReturnType::Type(_, ty) => ty,
+        ReturnType::Default => return Err(syn::Error::new_spanned(
+            &sig.ident,
+            "A provider has to return the provided value, e.g. `-> Result<Pool, Error>`"
+        ))
+    };
+
+    if let syn::Type::Path(p) = &**ty {
+        let last = p.path.segments.last();
+        if let Some(segment) = last.filter(|s| s.ident == RESULT) {
+            if let syn::PathArguments::AngleBracketed(args) = &segment.arguments {
+                if let Some(syn::GenericArgument::Type(ok)) = args.args.first() {
+                    return Ok((ok.clone(), true));
+                }
+            }
+        }
+    }
+
+    Ok(((**ty).clone(), false))
+}
+
+/// A compile error, pointing at the return type, unless it's a `Result` of the provided type
+fn build_result_check(sig: &syn::Signature, provided_ty: &syn::Type) -> TokenStream2 {
+    let ty = match &sig.output {
+        ReturnType::Type(_, ty) => ty,
+        ReturnType::Default => return quote! {}
+    };
+    let span = ty.span();
+    quote_spanned! {span=>
+        const _: fn() = || {
+            fn provides<R: sai::ProviderResult<Ok = #provided_ty>>() {}
+            provides::<#ty>();
+        };
+    }
+}
@@ -7,6 +7,21 @@ pub struct Symbol(&'static str);
 
 pub const INJECTED: Symbol = Symbol("injected");
 pub const LIFECYCLE: Symbol = Symbol("lifecycle");
+pub const COMPONENT: Symbol = Symbol("component");
+pub const SCOPE: Symbol = Symbol("scope");
+pub const CONSTRUCTOR: Symbol = Symbol("constructor");
+pub const TEARDOWN: Symbol = Symbol("teardown");
+pub const AFTER: Symbol = Symbol("after");
+pub const LAZY: Symbol = Symbol("lazy");
+pub const DEFERRED: Symbol = Symbol("deferred");
+pub const PROFILE: Symbol = Symbol("profile");
+pub const ENABLED_IF: Symbol = Symbol("enabled_if");
+
+pub const INJECTED_TYPE: Symbol = Symbol("Injected");
+pub const LAZY_TYPE: Symbol = Symbol("Lazy");
+pub const DEFERRED_TYPE: Symbol = Symbol("Deferred");
+pub const SYSTEM_HANDLE_TYPE: Symbol = Symbol("SystemHandle");
+pub const RESULT: Symbol = Symbol("Result");
 
 impl PartialEq<Symbol> for Ident {
     fn eq(&self, word: &Symbol) -> bool {